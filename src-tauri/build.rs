@@ -1,49 +1,151 @@
 use std::process::Command;
 use std::env;
+use std::fs;
 use std::path::Path;
 
+/// 通用二进制包含的两种架构（swiftc/lipo 的命名）
+const UNIVERSAL_ARCHS: [&str; 2] = ["arm64", "x86_64"];
+/// 未设置 MACOSX_DEPLOYMENT_TARGET 时的最低系统版本；Vision 的文字识别需要 10.15
+const DEFAULT_DEPLOYMENT_TARGET: &str = "10.15";
+
+/// OCR 可执行文件是否比 Swift 源文件新，并且是以相同的配置（debug/release、最低系统版本）编译的
+fn is_up_to_date(executable: &Path, profile_stamp: &Path, swift_src: &Path, expected: &str) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    match (modified(executable), modified(swift_src)) {
+        (Some(executable_time), Some(src_time)) if executable_time > src_time => {
+            fs::read_to_string(profile_stamp).map(|stamp| stamp == expected).unwrap_or(false)
+        }
+        _ => false,
+    }
+}
+
+/// 为单一架构编译 Swift 程序；release 配置下开启优化
+fn compile_slice(swift_src: &Path, output: &Path, arch: &str, deployment_target: &str, profile: &str) -> std::io::Result<std::process::Output> {
+    let mut cmd = Command::new("swiftc");
+    if profile == "release" {
+        cmd.arg("-O").arg("-whole-module-optimization");
+    }
+    cmd.arg("-target")
+        .arg(format!("{}-apple-macos{}", arch, deployment_target))
+        .arg("-o")
+        .arg(output)
+        .arg(swift_src)
+        .output()
+}
+
+/// 由 proto/ocr.proto 生成 prost 消息定义，输出到 OUT_DIR/ocr.rs，由 src/ocr_proto.rs 引入
+#[cfg(feature = "protobuf")]
+fn compile_protos() {
+    let file_descriptors = protox::compile(["proto/ocr.proto"], ["proto"])
+        .unwrap_or_else(|e| panic!("Failed to parse proto/ocr.proto: {}", e));
+    prost_build::Config::new()
+        .compile_fds(file_descriptors)
+        .unwrap_or_else(|e| panic!("Failed to generate protobuf code: {}", e));
+    println!("cargo:rerun-if-changed=proto/ocr.proto");
+}
+
 fn main() {
     tauri_build::build();
+    #[cfg(feature = "protobuf")]
+    compile_protos();
     // 只在 macOS 上编译 Swift OCR 程序
     if env::var("CARGO_CFG_TARGET_OS").unwrap_or_default() == "macos" {
         let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
         let target_dir = env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".to_string());
+        let profile = env::var("PROFILE").unwrap_or_default();
+        let deployment_target = env::var("MACOSX_DEPLOYMENT_TARGET").unwrap_or_else(|_| DEFAULT_DEPLOYMENT_TARGET.to_string());
+        // 当前构建目标的架构必须编译成功，另一种架构失败时退回单架构
+        let target_arch = match env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default().as_str() {
+            "aarch64" => "arm64".to_string(),
+            arch => arch.to_string(),
+        };
         
         // Swift 源文件路径
         let swift_src = Path::new(&manifest_dir).join("src/ocr.swift");
         // 输出可执行文件路径 (在 target 目录中)
         let ocr_executable = Path::new(&target_dir).join("ocr");
+        // 记录可执行文件是以哪种配置编译的，切换 debug/release 或最低系统版本时需要重新编译
+        let profile_stamp = Path::new(&target_dir).join("ocr.profile");
+        let stamp = format!("{} {}", profile, deployment_target);
         
         // 确保 src 目录存在
-        if swift_src.exists() {
+        if !swift_src.exists() {
+            panic!("Swift source file not found: {}", swift_src.display());
+        }
+        
+        if is_up_to_date(&ocr_executable, &profile_stamp, &swift_src, &stamp) {
+            println!("cargo:warning=Swift OCR program is up to date, skipping compilation");
+        } else {
             println!("cargo:warning=Compiling Swift OCR program...");
             
-            // 编译 Swift 程序
-            let output = Command::new("swiftc")
-                .arg("-o")
-                .arg(&ocr_executable)
-                .arg(&swift_src)
-                .output();
-                
-            match output {
-                Ok(output) => {
-                    if output.status.success() {
-                        println!("cargo:warning=Swift OCR program compiled successfully");
-                        // 将可执行文件复制到最终的 bundle 目录
-                        println!("cargo:rustc-env=OCR_EXECUTABLE_PATH={}", ocr_executable.display());
-                    } else {
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        println!("cargo:warning=Failed to compile Swift OCR program: {}", stderr);
+            let slice_path = |arch: &str| Path::new(&target_dir).join(format!("ocr-{}", arch));
+            let target_slice = slice_path(&target_arch);
+
+            // 编译失败时直接让构建失败，避免打包出无法使用的 OCR
+            match compile_slice(&swift_src, &target_slice, &target_arch, &deployment_target, &profile) {
+                Ok(output) if output.status.success() => {
+                    let mut slices = vec![target_slice.clone()];
+                    for arch in UNIVERSAL_ARCHS.iter().filter(|arch| **arch != target_arch) {
+                        let slice = slice_path(arch);
+                        match compile_slice(&swift_src, &slice, arch, &deployment_target, &profile) {
+                            Ok(output) if output.status.success() => slices.push(slice),
+                            Ok(output) => println!(
+                                "cargo:warning=Failed to compile {} slice of Swift OCR program, building {} only: {}",
+                                arch,
+                                target_arch,
+                                String::from_utf8_lossy(&output.stderr).trim()
+                            ),
+                            Err(e) => println!(
+                                "cargo:warning=Failed to compile {} slice of Swift OCR program, building {} only: {}",
+                                arch, target_arch, e
+                            ),
+                        }
                     }
+
+                    // 用 lipo 合并为通用二进制；只有一种架构或 lipo 失败时直接使用当前架构
+                    let universal = slices.len() > 1 && match Command::new("lipo")
+                        .arg("-create")
+                        .args(&slices)
+                        .arg("-output")
+                        .arg(&ocr_executable)
+                        .output()
+                    {
+                        Ok(output) if output.status.success() => true,
+                        Ok(output) => {
+                            println!("cargo:warning=lipo failed, building {} only: {}", target_arch, String::from_utf8_lossy(&output.stderr).trim());
+                            false
+                        }
+                        Err(e) => {
+                            println!("cargo:warning=Failed to execute lipo, building {} only: {}", target_arch, e);
+                            false
+                        }
+                    };
+                    if !universal {
+                        if let Err(e) = fs::copy(&target_slice, &ocr_executable) {
+                            panic!("Failed to copy Swift OCR program: {}", e);
+                        }
+                    }
+
+                    println!("cargo:warning=Swift OCR program compiled successfully");
+                    let _ = fs::write(&profile_stamp, &stamp);
+                }
+                Ok(output) => {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    panic!("Failed to compile Swift OCR program: {}", stderr);
+                }
+                // 在非 macOS 主机上交叉检查（cargo check/clippy --target）时没有 swiftc，只给出警告
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound && !cfg!(target_os = "macos") => {
+                    println!("cargo:warning=swiftc not found, skipping Swift OCR program: {}", e);
                 }
                 Err(e) => {
-                    println!("cargo:warning=Failed to execute swiftc: {}", e);
+                    panic!("Failed to execute swiftc: {}", e);
                 }
             }
-        } else {
-            println!("cargo:warning=Swift source file not found: {}", swift_src.display());
         }
         
+        // 将可执行文件复制到最终的 bundle 目录
+        println!("cargo:rustc-env=OCR_EXECUTABLE_PATH={}", ocr_executable.display());
         println!("cargo:rerun-if-changed=src/ocr.swift");
+        println!("cargo:rerun-if-env-changed=MACOSX_DEPLOYMENT_TARGET");
     }
-}
\ No newline at end of file
+}
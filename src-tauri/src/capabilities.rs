@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::{ocr, tts};
+
+/// 当前平台支持的功能，供前端隐藏不可用的界面，而不是调用后再根据错误判断
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Capabilities {
+    pub ocr: bool,         // 平台是否支持 extract_text_with_system_ocr 等OCR命令
+    pub tts: bool,         // 平台是否支持 speak_text 等朗读命令
+    pub tts_to_file: bool, // synthesize_to_file
+    pub platform: String,  // "macos"、"windows"、"linux" 等
+    // 以下为实际检查的结果：平台支持但后端未安装或无法运行时为 false，原因见 notes
+    pub ocr_available: bool,
+    pub ocr_backend: String,        // "Vision"、"Windows.Media.Ocr"、"tesseract"，不支持的平台为 "none"
+    pub ocr_languages: Vec<String>, // 可识别的语言，OCR不可用时为空
+    pub tts_available: bool,
+    pub tts_backend: String, // "say"、"Windows.Media.SpeechSynthesis"、"spd-say"、"espeak-ng"，不可用时为 "none"
+    pub notes: Vec<String>,  // 不可用的原因等说明，可直接显示给用户
+}
+
+/// 检查当前平台的OCR和朗读后端，会运行 ocr、say、tesseract 等程序，耗时可能达到数百毫秒，应在启动时调用一次
+/// 平台判断与各命令保持一致；Linux 上需安装 tesseract 和 spd-say/espeak-ng，未安装时 *_available 为 false
+#[command]
+pub async fn get_capabilities() -> Capabilities {
+    let desktop = cfg!(any(target_os = "macos", target_os = "windows", target_os = "linux"));
+    let mut notes = Vec::new();
+    
+    // 两项检查都会同步等待外部程序或 WinRT 调用，放到阻塞线程池中执行，避免占用异步运行时的工作线程
+    let ocr_check = tauri::async_runtime::spawn_blocking(|| tauri::async_runtime::block_on(ocr::check_ocr_backend()))
+        .await
+        .unwrap_or_else(|e| Err((ocr::OcrError::EngineFailure, format!("OCR check panicked: {}", e))));
+    let (ocr_available, ocr_languages) = match ocr_check {
+        Ok(languages) => {
+            if languages.is_empty() {
+                notes.push("No OCR languages are installed".to_string());
+            }
+            (!languages.is_empty(), languages)
+        }
+        Err((_, e)) => {
+            notes.push(format!("OCR is unavailable: {}", e));
+            (false, vec![])
+        }
+    };
+    
+    let tts_check = tauri::async_runtime::spawn_blocking(tts::check_tts_backend)
+        .await
+        .unwrap_or_else(|e| Err(format!("TTS check panicked: {}", e)));
+    let (tts_available, tts_backend) = match tts_check {
+        Ok((backend, 0)) => {
+            notes.push(format!("No voices are installed for {}", backend));
+            (false, backend)
+        }
+        Ok((backend, _)) => (true, backend),
+        Err(e) => {
+            notes.push(format!("TTS is unavailable: {}", e));
+            (false, "none")
+        }
+    };
+    
+    let tts_to_file = cfg!(target_os = "macos");
+    if desktop && !tts_to_file {
+        notes.push("Saving speech to a file is only available on macOS".to_string());
+    }
+    
+    Capabilities {
+        ocr: desktop,
+        tts: desktop,
+        tts_to_file,
+        platform: std::env::consts::OS.to_string(),
+        ocr_available,
+        ocr_backend: ocr::OCR_BACKEND.to_string(),
+        ocr_languages,
+        tts_available,
+        tts_backend: tts_backend.to_string(),
+        notes,
+    }
+}
@@ -0,0 +1,379 @@
+use std::io::Cursor;
+
+use image::{DynamicImage, ImageFormat, Luma, Rgba, RgbaImage};
+use imageproc::geometric_transformations::{warp_into_with, Interpolation};
+
+use crate::ocr::{Point, PreprocessOptions, Region};
+
+// 各图片格式的文件头；WebP 另外判断（RIFF 容器中的 WEBP 类型）
+const IMAGE_SIGNATURES: &[(&[u8], ImageFormat)] = &[
+    (b"\x89PNG\r\n\x1a\n", ImageFormat::Png),
+    (b"\xff\xd8\xff", ImageFormat::Jpeg),
+    (b"BM", ImageFormat::Bmp),
+    (b"II*\0", ImageFormat::Tiff),
+    (b"MM\0*", ImageFormat::Tiff),
+];
+
+/// 根据文件头判断图片格式，只识别各OCR后端都能读取的 PNG、JPEG、BMP、TIFF 和 WebP，其他格式返回 None
+pub fn detect_image_format(image_data: &[u8]) -> Option<ImageFormat> {
+    if image_data.starts_with(b"RIFF") && image_data.get(8..12) == Some(b"WEBP".as_slice()) {
+        return Some(ImageFormat::WebP);
+    }
+    IMAGE_SIGNATURES
+        .iter()
+        .find(|(signature, _)| image_data.starts_with(signature))
+        .map(|(_, format)| *format)
+}
+
+/// 解码图片字节
+pub fn decode_image(image_data: &[u8]) -> Result<DynamicImage, String> {
+    image::load_from_memory(image_data).map_err(|e| format!("Failed to decode image: {}", e))
+}
+
+/// 将图片编码为 PNG 字节，供OCR后端使用
+pub fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    Ok(buffer)
+}
+
+fn cross(o: &Point, a: &Point, b: &Point) -> f64 {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+/// 检查四边形：顶点必须位于图片范围内（归一化坐标 0..1），且为面积不为零的凸四边形
+pub fn validate_polygon(polygon: &[Point; 4]) -> Result<(), String> {
+    if polygon
+        .iter()
+        .any(|p| !(0.0..=1.0).contains(&p.x) || !(0.0..=1.0).contains(&p.y))
+    {
+        return Err("Region polygon must lie within the image (coordinates in 0..1)".to_string());
+    }
+
+    // 相邻两条边的叉积符号一致时为凸四边形；顶点重合或共线时叉积为零
+    let turns: Vec<f64> = (0..4)
+        .map(|i| cross(&polygon[i], &polygon[(i + 1) % 4], &polygon[(i + 2) % 4]))
+        .collect();
+    let convex = turns.iter().all(|&t| t > 0.0) || turns.iter().all(|&t| t < 0.0);
+
+    // 鞋带公式计算面积
+    let area = (0..4)
+        .map(|i| {
+            let (a, b) = (&polygon[i], &polygon[(i + 1) % 4]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        .abs()
+        / 2.0;
+
+    if !convex || area < 1e-4 {
+        return Err("Region polygon must be a non-degenerate convex quadrilateral".to_string());
+    }
+
+    Ok(())
+}
+
+/// 检查矩形区域：必须位于图片范围内（归一化坐标 0..1），且宽高都大于零
+pub fn validate_region(region: &Region) -> Result<(), String> {
+    let Region { x, y, width, height } = *region;
+    if ![x, y, width, height].iter().all(|value| value.is_finite()) || width <= 0.0 || height <= 0.0 {
+        return Err("Region must have a positive width and height".to_string());
+    }
+    // 允许浮点误差，例如 0.1 + 0.9 略大于 1
+    if x < 0.0 || y < 0.0 || x + width > 1.0 + 1e-9 || y + height > 1.0 + 1e-9 {
+        return Err("Region must lie within the image (coordinates in 0..1)".to_string());
+    }
+    Ok(())
+}
+
+/// 裁剪图片中的矩形区域
+/// 返回裁剪后的 PNG 字节，以及从裁剪后图片的归一化坐标映射回原图归一化坐标的变换（与 warp_quadrilateral 的格式相同）
+pub fn crop_region(image_data: &[u8], region: &Region) -> Result<(Vec<u8>, [f64; 9]), String> {
+    validate_region(region)?;
+
+    let source = decode_image(image_data)?;
+    let (source_width, source_height) = (source.width(), source.height());
+
+    // 向外取整到整像素，区域很小时至少保留一个像素
+    let left = ((region.x * source_width as f64).floor() as u32).min(source_width.saturating_sub(1));
+    let top = ((region.y * source_height as f64).floor() as u32).min(source_height.saturating_sub(1));
+    let right = (((region.x + region.width) * source_width as f64).ceil() as u32).clamp(left + 1, source_width.max(1));
+    let bottom = (((region.y + region.height) * source_height as f64).ceil() as u32).clamp(top + 1, source_height.max(1));
+
+    let cropped = source.crop_imm(left, top, right - left, bottom - top);
+
+    // 按实际裁剪的像素范围计算变换，识别结果的坐标映射回原图时没有取整误差
+    let (source_width, source_height) = (source_width as f64, source_height as f64);
+    let transform = [
+        (right - left) as f64 / source_width, 0.0, left as f64 / source_width,
+        0.0, (bottom - top) as f64 / source_height, top as f64 / source_height,
+        0.0, 0.0, 1.0,
+    ];
+
+    Ok((encode_png(&cropped)?, transform))
+}
+
+/// 计算将单位正方形映射到四边形的单应矩阵（按行排列的 3x3 矩阵）
+/// 四边形顶点依次对应单位正方形的 (0,0)、(1,0)、(1,1)、(0,1)，即左上、右上、右下、左下
+pub fn quad_homography(polygon: &[Point; 4]) -> [f64; 9] {
+    let [p0, p1, p2, p3] = polygon;
+    let (dx1, dx2, dx3) = (p1.x - p2.x, p3.x - p2.x, p0.x - p1.x + p2.x - p3.x);
+    let (dy1, dy2, dy3) = (p1.y - p2.y, p3.y - p2.y, p0.y - p1.y + p2.y - p3.y);
+
+    // 平行四边形时退化为仿射变换
+    let (g, h) = if dx3.abs() < 1e-12 && dy3.abs() < 1e-12 {
+        (0.0, 0.0)
+    } else {
+        let den = dx1 * dy2 - dx2 * dy1;
+        ((dx3 * dy2 - dx2 * dy3) / den, (dx1 * dy3 - dx3 * dy1) / den)
+    };
+
+    [
+        p1.x - p0.x + g * p1.x, p3.x - p0.x + h * p3.x, p0.x,
+        p1.y - p0.y + g * p1.y, p3.y - p0.y + h * p3.y, p0.y,
+        g, h, 1.0,
+    ]
+}
+
+/// 用单应矩阵变换一个点
+pub fn apply_homography(matrix: &[f64; 9], x: f64, y: f64) -> (f64, f64) {
+    let w = matrix[6] * x + matrix[7] * y + matrix[8];
+    (
+        (matrix[0] * x + matrix[1] * y + matrix[2]) / w,
+        (matrix[3] * x + matrix[4] * y + matrix[5]) / w,
+    )
+}
+
+/// 组合两个单应矩阵：结果先应用 inner，再应用 outer
+pub fn compose_homography(outer: &[f64; 9], inner: &[f64; 9]) -> [f64; 9] {
+    let mut result = [0.0; 9];
+    for row in 0..3 {
+        for column in 0..3 {
+            result[row * 3 + column] = (0..3).map(|k| outer[row * 3 + k] * inner[k * 3 + column]).sum();
+        }
+    }
+    result
+}
+
+/// 将图片顺时针旋转 degrees 度（0、90、180 或 270）
+/// 返回旋转后的 PNG 字节，以及从旋转后图片的归一化坐标映射回旋转前归一化坐标的矩阵
+pub fn rotate(image: &DynamicImage, degrees: u16) -> Result<(Vec<u8>, [f64; 9]), String> {
+    let (rotated, transform) = match degrees {
+        0 => (image.clone(), [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]),
+        90 => (image.rotate90(), [0.0, 1.0, 0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 1.0]),
+        180 => (image.rotate180(), [-1.0, 0.0, 1.0, 0.0, -1.0, 1.0, 0.0, 0.0, 1.0]),
+        270 => (image.rotate270(), [0.0, -1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0]),
+        _ => return Err(format!("Unsupported rotation {}: expected 0, 90, 180 or 270", degrees)),
+    };
+    Ok((encode_png(&rotated)?, transform))
+}
+
+/// 将图片中的四边形区域透视校正为正立的矩形
+/// 返回校正后的 PNG 字节，以及从校正后图片的归一化坐标映射回原图归一化坐标的单应矩阵
+pub fn warp_quadrilateral(image_data: &[u8], polygon: &[Point; 4]) -> Result<(Vec<u8>, [f64; 9]), String> {
+    validate_polygon(polygon)?;
+
+    let source = decode_image(image_data)?.to_rgba8();
+    let (source_width, source_height) = (source.width() as f64, source.height() as f64);
+
+    // 输出尺寸取四边形对边长度（像素）的较大值
+    let distance = |a: &Point, b: &Point| {
+        ((a.x - b.x) * source_width).hypot((a.y - b.y) * source_height)
+    };
+    let [p0, p1, p2, p3] = polygon;
+    let width = distance(p0, p1).max(distance(p3, p2)).round().max(1.0) as u32;
+    let height = distance(p0, p3).max(distance(p1, p2)).round().max(1.0) as u32;
+
+    let matrix = quad_homography(polygon);
+    let mut warped = RgbaImage::new(width, height);
+    warp_into_with(
+        &source,
+        |x, y| {
+            let (u, v) = apply_homography(&matrix, x as f64 / width as f64, y as f64 / height as f64);
+            ((u * source_width) as f32, (v * source_height) as f32)
+        },
+        Interpolation::Bilinear,
+        Rgba([255, 255, 255, 255]),
+        &mut warped,
+    );
+
+    Ok((encode_png(&DynamicImage::ImageRgba8(warped))?, matrix))
+}
+
+/// 识别前预处理图片，依次做灰度化、对比度调整和二值化，返回处理后的 PNG 字节
+pub fn preprocess(image_data: &[u8], options: &PreprocessOptions) -> Result<Vec<u8>, String> {
+    let mut image = decode_image(image_data)?;
+
+    if options.grayscale {
+        image = DynamicImage::ImageLuma8(image.to_luma8());
+    }
+    if let Some(contrast) = options.contrast {
+        image = image.adjust_contrast(contrast);
+    }
+    if let Some(threshold) = options.threshold {
+        let mut luma = image.to_luma8();
+        for Luma([value]) in luma.pixels_mut() {
+            *value = if *value >= threshold { 255 } else { 0 };
+        }
+        image = DynamicImage::ImageLuma8(luma);
+    }
+
+    encode_png(&image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn detects_supported_formats_by_signature() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(2, 2));
+        for format in [ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::Bmp, ImageFormat::Tiff, ImageFormat::WebP] {
+            let mut encoded = Cursor::new(Vec::new());
+            // JPEG 不支持透明通道
+            let image = if format == ImageFormat::Jpeg { DynamicImage::ImageRgb8(image.to_rgb8()) } else { image.clone() };
+            image.write_to(&mut encoded, format).unwrap();
+            assert_eq!(detect_image_format(encoded.get_ref()), Some(format), "{:?}", format);
+        }
+        assert_eq!(detect_image_format(b"MM\0*rest"), Some(ImageFormat::Tiff));
+    }
+
+    #[test]
+    fn rejects_unknown_or_truncated_data() {
+        for data in [
+            b"GIF89a".as_slice(),
+            b"%PDF-1.7",
+            b"",
+            b"\x89PN",
+            b"RIFF\0\0\0\0WAVE",
+            b"RIFF",
+            b"<svg xmlns=",
+        ] {
+            assert_eq!(detect_image_format(data), None, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn homography_maps_unit_square_to_quad() {
+        // 透视变形的四边形：左上、右上、右下、左下
+        let polygon = [point(0.2, 0.1), point(0.8, 0.2), point(0.9, 0.9), point(0.1, 0.8)];
+        let matrix = quad_homography(&polygon);
+        for ((u, v), corner) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].into_iter().zip(&polygon) {
+            assert_close(apply_homography(&matrix, u, v), (corner.x, corner.y));
+        }
+        // 透视变换下正方形中心不映射到顶点的平均值，而是对角线的交点
+        let (x, y) = apply_homography(&matrix, 0.5, 0.5);
+        assert!((x - 0.5).abs() > 1e-6 || (y - 0.5).abs() > 1e-6);
+    }
+
+    #[test]
+    fn homography_of_parallelogram_is_affine() {
+        let polygon = [point(0.1, 0.1), point(0.6, 0.2), point(0.7, 0.7), point(0.2, 0.6)];
+        let matrix = quad_homography(&polygon);
+        assert_eq!(&matrix[6..], &[0.0, 0.0, 1.0]);
+        assert_close(apply_homography(&matrix, 0.5, 0.5), (0.4, 0.4));
+    }
+
+    #[test]
+    fn composed_rotations_cancel_out() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(4, 2));
+        let (_, rotate90) = rotate(&image, 90).unwrap();
+        let (_, rotate270) = rotate(&image, 270).unwrap();
+        let identity = compose_homography(&rotate90, &rotate270);
+        assert_close(apply_homography(&identity, 0.25, 0.75), (0.25, 0.75));
+        assert!(rotate(&image, 45).is_err());
+    }
+
+    #[test]
+    fn warps_quadrilateral_to_rectangle() {
+        // 左半边红色、右半边蓝色；四边形完全位于右半边
+        let red = Rgba([255, 0, 0, 255]);
+        let blue = Rgba([0, 0, 255, 255]);
+        let image = RgbaImage::from_fn(200, 100, |x, _| if x < 100 { red } else { blue });
+        let image_data = encode_png(&DynamicImage::ImageRgba8(image)).unwrap();
+        let polygon = [point(0.55, 0.1), point(0.95, 0.2), point(0.9, 0.9), point(0.6, 0.8)];
+
+        let (warped, matrix) = warp_quadrilateral(&image_data, &polygon).unwrap();
+        let warped = decode_image(&warped).unwrap().to_rgba8();
+        // 输出尺寸取对边长度（像素）的较大值
+        assert_eq!((warped.width(), warped.height()), (81, 71));
+        assert!(warped.pixels().all(|pixel| pixel[2] > pixel[0]), "quad should only contain blue pixels");
+        // 返回的矩阵把校正后图片的坐标映射回原图
+        assert_close(apply_homography(&matrix, 0.0, 0.0), (0.55, 0.1));
+        assert_close(apply_homography(&matrix, 1.0, 1.0), (0.9, 0.9));
+    }
+
+    #[test]
+    fn rejects_invalid_polygons() {
+        // 自相交（顶点顺序错误）的四边形
+        let polygon = [point(0.1, 0.1), point(0.9, 0.9), point(0.9, 0.1), point(0.1, 0.9)];
+        assert!(warp_quadrilateral(&[], &polygon).is_err());
+        assert!(validate_polygon(&[point(0.1, 0.1), point(0.9, 0.1), point(0.9, 0.9), point(0.1, 0.9)]).is_ok());
+    }
+
+    /// 低对比度的偏色文档：左半边为深一些的“文字”，右半边为背景
+    fn low_contrast_page() -> Vec<u8> {
+        let image = RgbaImage::from_fn(8, 4, |x, _| {
+            if x < 4 { Rgba([120, 110, 95, 255]) } else { Rgba([150, 140, 125, 255]) }
+        });
+        encode_png(&DynamicImage::ImageRgba8(image)).unwrap()
+    }
+
+    #[test]
+    fn preprocess_increases_low_contrast() {
+        let options = PreprocessOptions {
+            grayscale: true,
+            contrast: Some(30.0),
+            threshold: None,
+        };
+        let processed = decode_image(&preprocess(&low_contrast_page(), &options).unwrap()).unwrap();
+        // 灰度化后只剩一个通道
+        assert_eq!(processed.color(), image::ColorType::L8);
+        let processed = processed.to_luma8();
+        let (text, background) = (processed.get_pixel(0, 0)[0], processed.get_pixel(7, 0)[0]);
+
+        let original = decode_image(&low_contrast_page()).unwrap().to_luma8();
+        let original_spread = original.get_pixel(7, 0)[0] - original.get_pixel(0, 0)[0];
+        assert!(background > text);
+        assert!(background - text > original_spread, "{} -> {}", original_spread, background - text);
+    }
+
+    #[test]
+    fn preprocess_threshold_binarizes() {
+        let options = PreprocessOptions {
+            grayscale: false,
+            contrast: None,
+            threshold: Some(128),
+        };
+        let processed = decode_image(&preprocess(&low_contrast_page(), &options).unwrap()).unwrap().to_luma8();
+        assert_eq!(processed.get_pixel(0, 0)[0], 0);
+        assert_eq!(processed.get_pixel(7, 0)[0], 255);
+        assert!(processed.pixels().all(|Luma([value])| *value == 0 || *value == 255));
+    }
+
+    #[test]
+    fn preprocess_without_options_keeps_pixels() {
+        let options = PreprocessOptions {
+            grayscale: false,
+            contrast: None,
+            threshold: None,
+        };
+        let processed = decode_image(&preprocess(&low_contrast_page(), &options).unwrap()).unwrap();
+        assert_eq!(processed.to_rgba8(), decode_image(&low_contrast_page()).unwrap().to_rgba8());
+    }
+}
@@ -0,0 +1,221 @@
+use crate::ocr::{is_cjk_char, OcrLine};
+
+// 宽度不小于页面宽度该比例的行视为跨栏的行（标题、通栏段落），不参与分栏检测
+const SPANNING_WIDTH: f64 = 0.6;
+// 分栏检测时将页面宽度划分的格数
+const COLUMN_BINS: usize = 100;
+// 覆盖某一格的行数不超过最多处的该比例时，该格视为栏间空白
+const GUTTER_RATIO: f64 = 0.1;
+// 相邻两行之间的垂直空白超过行高的该比例时视为段落间距
+const PARAGRAPH_GAP_RATIO: f64 = 0.8;
+// 句末标点，行以这些标点结尾时视为段落结束
+const SENTENCE_ENDINGS: &[char] = &['.', '!', '?', '。', '！', '？', '…'];
+// 句末标点之后可能出现的右引号和右括号
+const CLOSING_MARKS: &[char] = &['"', '\'', '”', '’', '」', '』', ')', '）'];
+
+/// 检测分栏：返回每一栏的水平范围 (左, 右)，归一化坐标
+/// 统计较窄的行在水平方向上覆盖每一格的次数，覆盖次数很少的格是栏间空白，
+/// 连续的非空白格组成一栏；个别横跨栏间空白的浮动元素（例如图注）不会把两栏合并
+fn detect_columns(lines: &[OcrLine]) -> Vec<(f64, f64)> {
+    let mut coverage = [0usize; COLUMN_BINS];
+    for line in lines.iter().filter(|line| line.width < SPANNING_WIDTH) {
+        let start = ((line.x * COLUMN_BINS as f64).floor().max(0.0) as usize).min(COLUMN_BINS);
+        let end = (((line.x + line.width) * COLUMN_BINS as f64).ceil().max(0.0) as usize).min(COLUMN_BINS);
+        for count in &mut coverage[start..end] {
+            *count += 1;
+        }
+    }
+
+    let max = coverage.iter().copied().max().unwrap_or(0);
+    // 向上取整：行数较少时阈值至少为 1，单个浮动元素不足以填满栏间空白
+    let threshold = (max as f64 * GUTTER_RATIO).ceil() as usize;
+
+    let mut columns = Vec::new();
+    let mut column_start = None;
+    for (bin, &count) in coverage.iter().enumerate() {
+        match (count > threshold, column_start) {
+            (true, None) => column_start = Some(bin),
+            (false, Some(start)) => {
+                columns.push((start as f64 / COLUMN_BINS as f64, bin as f64 / COLUMN_BINS as f64));
+                column_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = column_start {
+        columns.push((start as f64 / COLUMN_BINS as f64, 1.0));
+    }
+    columns
+}
+
+/// 两行在垂直方向上是否明显重叠（重叠部分超过较矮一行高度的一半）
+fn overlaps_vertically(a: &OcrLine, b: &OcrLine) -> bool {
+    let overlap = (a.y + a.height).min(b.y + b.height) - a.y.max(b.y);
+    overlap > a.height.min(b.height) / 2.0
+}
+
+/// 估计每一行在阅读顺序中的位置是否可靠，返回与 lines 一一对应的置信度（0..1）
+///
+/// 启发式规则：
+/// - 较窄的行：置信度为该行落在所属栏（重叠最多的一栏）内的宽度比例，
+///   横跨栏间空白的行（例如浮动的图注）置信度较低；
+///   多栏页面中所属栏只有这一行时，它很可能是浮动元素，置信度再减半
+/// - 跨栏的行：与其他行在垂直方向上不重叠时置信度为 1，
+///   否则说明它与分栏内容交错，不确定应该插在哪一栏之后，置信度为 0.5
+///
+/// 单栏页面的阅读顺序就是从上到下，所有行的置信度都接近 1
+pub fn reading_order_confidence(lines: &[OcrLine]) -> Vec<f32> {
+    let columns = detect_columns(lines);
+
+    // 每一栏中以中心点计算的行数
+    let column_of = |line: &OcrLine| {
+        let center = line.x + line.width / 2.0;
+        columns.iter().position(|&(left, right)| center >= left && center <= right)
+    };
+    let mut column_sizes = vec![0usize; columns.len()];
+    for line in lines {
+        if let Some(column) = column_of(line) {
+            column_sizes[column] += 1;
+        }
+    }
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            if line.width >= SPANNING_WIDTH || columns.is_empty() {
+                let interleaved = lines
+                    .iter()
+                    .enumerate()
+                    .any(|(other, other_line)| other != index && overlaps_vertically(line, other_line));
+                return if interleaved { 0.5 } else { 1.0 };
+            }
+
+            let containment = if line.width > 0.0 {
+                columns
+                    .iter()
+                    .map(|&(left, right)| ((line.x + line.width).min(right) - line.x.max(left)).max(0.0))
+                    .fold(0.0, f64::max)
+                    / line.width
+            } else {
+                1.0
+            };
+
+            let floating = columns.len() > 1 && column_of(line).map_or(true, |column| column_sizes[column] <= 1);
+            let confidence = if floating { containment / 2.0 } else { containment };
+            confidence.clamp(0.0, 1.0) as f32
+        })
+        .collect()
+}
+
+/// 一行是否以句末标点结尾（忽略其后的右引号和右括号）
+fn ends_sentence(text: &str) -> bool {
+    text.trim_end().trim_end_matches(CLOSING_MARKS).ends_with(SENTENCE_ENDINGS)
+}
+
+/// 两行之间是否有明显大于行距的垂直空白
+fn has_paragraph_gap(previous: &OcrLine, next: &OcrLine) -> bool {
+    let gap = next.y - (previous.y + previous.height);
+    gap > previous.height.min(next.height) * PARAGRAPH_GAP_RATIO
+}
+
+/// 行尾的连字符是否为单词断行：连字符前是字母，下一行以小写字母开头
+/// 合并段落（merge_paragraphs）和去连字符（text::dehyphenate）共用这条规则，避免误合并列表项和复合词
+pub(crate) fn is_split_word(line: &str, next_line: &str) -> bool {
+    let mut tail = line.chars().rev();
+    tail.next() == Some('-')
+        && tail.next().is_some_and(char::is_alphabetic)
+        && next_line.chars().next().is_some_and(char::is_lowercase)
+}
+
+/// 将折行的各行合并为段落，段落之间以换行符分隔
+///
+/// 相邻两行属于同一段落，除非上一行以句末标点结尾，或两行之间的垂直空白明显大于行距。
+/// 合并时拉丁文字之间补一个空格，中日韩文字之间直接连接；
+/// 行尾连字符为单词断行时（规则见 is_split_word）合并时去掉连字符
+pub fn merge_paragraphs(lines: &[OcrLine]) -> String {
+    let mut text = String::new();
+    let mut previous: Option<&OcrLine> = None;
+
+    for line in lines {
+        let current = line.text.trim();
+        if current.is_empty() {
+            continue;
+        }
+
+        if let Some(previous) = previous {
+            let last = text.chars().next_back();
+            if ends_sentence(&previous.text) || has_paragraph_gap(previous, line) {
+                text.push('\n');
+            } else if is_split_word(&text, current) {
+                text.pop();
+            } else if !(last.is_some_and(is_cjk_char) && current.chars().next().is_some_and(is_cjk_char)) {
+                text.push(' ');
+            }
+        }
+
+        text.push_str(current);
+        previous = Some(line);
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(x: f64, y: f64, width: f64) -> OcrLine {
+        OcrLine {
+            text: String::new(),
+            x,
+            y,
+            width,
+            height: 0.02,
+            confidence: None,
+            reading_order_confidence: 0.0,
+        }
+    }
+
+    /// 两栏页面：左右两栏各 count 行
+    fn two_columns(count: usize) -> Vec<OcrLine> {
+        let mut lines: Vec<OcrLine> = (0..count).map(|i| line(0.05, 0.2 + i as f64 * 0.03, 0.4)).collect();
+        lines.extend((0..count).map(|i| line(0.55, 0.2 + i as f64 * 0.03, 0.4)));
+        lines
+    }
+
+    #[test]
+    fn single_column_is_confident() {
+        let lines: Vec<OcrLine> = (0..5).map(|i| line(0.1, 0.1 + i as f64 * 0.03, 0.5)).collect();
+        assert_eq!(reading_order_confidence(&lines), vec![1.0; 5]);
+        assert!(reading_order_confidence(&[]).is_empty());
+    }
+
+    #[test]
+    fn two_columns_are_confident() {
+        let lines = two_columns(6);
+        assert_eq!(detect_columns(&lines).len(), 2);
+        assert_eq!(reading_order_confidence(&lines), vec![1.0; 12]);
+    }
+
+    #[test]
+    fn floating_caption_across_gutter_is_uncertain() {
+        let mut lines = two_columns(6);
+        // 横跨栏间空白的图注
+        lines.push(line(0.3, 0.5, 0.4));
+        let confidence = reading_order_confidence(&lines);
+        assert!(confidence[12] < 0.5, "{}", confidence[12]);
+        assert!(confidence[..12].iter().all(|&value| value == 1.0));
+    }
+
+    #[test]
+    fn spanning_lines_depend_on_interleaving() {
+        let mut lines = two_columns(6);
+        // 页面顶部的通栏标题与其他行不重叠；与分栏内容交错的通栏行无法确定位置
+        lines.push(line(0.05, 0.05, 0.9));
+        lines.push(line(0.05, 0.2, 0.9));
+        let confidence = reading_order_confidence(&lines);
+        assert_eq!(confidence[12], 1.0);
+        assert_eq!(confidence[13], 0.5);
+    }
+}
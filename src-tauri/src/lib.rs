@@ -1,7 +1,23 @@
+mod capabilities;
+mod image_ops;
+mod layout;
 mod ocr;
+mod ocr_cache;
+mod ocr_limit;
+mod pdf_render;
+#[cfg(feature = "protobuf")]
+mod ocr_proto;
+mod ssml;
+mod text;
 mod tts;
-use ocr::{extract_text_with_system_ocr, get_supported_recognition_languages};
-use tts::{speak_text, stop_speaking, get_supported_tts_languages, get_voices_for_language};
+use capabilities::get_capabilities;
+use ocr::{extract_text_with_system_ocr, extract_text_batch, extract_text_stream, get_supported_recognition_languages, detect_language, cancel_ocr, check_ocr_ready, ocr_pdf_page};
+use ocr_cache::{clear_ocr_cache, ocr_cache_stats};
+use ocr_limit::set_ocr_concurrency;
+#[cfg(feature = "protobuf")]
+use ocr_proto::{ocr_result_to_protobuf, ocr_result_from_protobuf};
+use text::{split_into_chapters, segment_for_reveal, repair_ocr_confusions};
+use tts::{speak_text, speak_text_with_options, enqueue_speech, clear_speech_queue, synthesize_to_file, stop_speaking, pause_speaking, resume_speaking, stop_all_speaking, list_active_tts, get_supported_tts_languages, get_voices_for_language, get_all_voices, set_pronunciations, select_fastest_voice};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -11,12 +27,40 @@ pub fn run() {
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .invoke_handler(tauri::generate_handler![
+            get_capabilities,
             extract_text_with_system_ocr,
+            extract_text_batch,
+            extract_text_stream,
             get_supported_recognition_languages,
+            detect_language,
+            cancel_ocr,
+            check_ocr_ready,
+            ocr_pdf_page,
+            clear_ocr_cache,
+            ocr_cache_stats,
+            set_ocr_concurrency,
             speak_text,
+            speak_text_with_options,
+            enqueue_speech,
+            clear_speech_queue,
+            synthesize_to_file,
             stop_speaking,
+            pause_speaking,
+            resume_speaking,
+            stop_all_speaking,
+            list_active_tts,
             get_supported_tts_languages,
-            get_voices_for_language
+            get_voices_for_language,
+            get_all_voices,
+            split_into_chapters,
+            segment_for_reveal,
+            repair_ocr_confusions,
+            set_pronunciations,
+            select_fastest_voice,
+            #[cfg(feature = "protobuf")]
+            ocr_result_to_protobuf,
+            #[cfg(feature = "protobuf")]
+            ocr_result_from_protobuf
         ])
         .setup(|app| {
             if cfg!(debug_assertions) {
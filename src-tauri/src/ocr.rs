@@ -1,12 +1,21 @@
+use base64::{alphabet, Engine as _, engine::{general_purpose, DecodePaddingMode, GeneralPurpose}};
 use serde::{Deserialize, Serialize};
-use tauri::command;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{command, Emitter};
 
-#[cfg(target_os = "macos")]
+use crate::ocr_cache::{self, CacheKey};
+use crate::ocr_limit;
+use crate::{image_ops, layout, pdf_render, text};
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
 use std::process::Command;
 
-/// 去除中文字符之间的空格
-/// 保留拉丁字母之间的空格，只去除中文字符与中文字符或中文标点之间的空格
-fn remove_chinese_spaces(text: &str) -> String {
+/// 去除中日韩文字之间的空格
+/// 保留拉丁字母之间的空格，只去除中日韩文字与中日韩文字或全角标点之间的空格
+/// 注意韩文本身用空格分词，只应在识别结果中混入了多余空格时启用
+fn collapse_cjk_spaces(text: &str) -> String {
     let chars: Vec<char> = text.chars().collect();
     let mut result = String::new();
     let mut i = 0;
@@ -16,22 +25,22 @@ fn remove_chinese_spaces(text: &str) -> String {
         
         // 如果当前字符是空格
         if current_char == ' ' {
-            // 检查前一个字符是否是中文字符或中文标点
-            let prev_is_chinese = if i > 0 {
-                is_chinese_char(chars[i - 1])
+            // 检查前一个字符是否是中日韩文字或标点
+            let prev_is_cjk = if i > 0 {
+                is_cjk_char(chars[i - 1])
             } else {
                 false
             };
             
-            // 检查后一个字符是否是中文字符或中文标点
-            let next_is_chinese = if i < chars.len() - 1 {
-                is_chinese_char(chars[i + 1])
+            // 检查后一个字符是否是中日韩文字或标点
+            let next_is_cjk = if i < chars.len() - 1 {
+                is_cjk_char(chars[i + 1])
             } else {
                 false
             };
             
-            // 只有当前后都是中文字符时才去除空格
-            if prev_is_chinese && next_is_chinese {
+            // 只有当前后都是中日韩文字时才去除空格
+            if prev_is_cjk && next_is_cjk {
                 // 去除空格，不添加到结果中
             } else {
                 // 保留空格
@@ -48,9 +57,9 @@ fn remove_chinese_spaces(text: &str) -> String {
     result
 }
 
-/// 判断字符是否为中文字符或中文标点
-fn is_chinese_char(c: char) -> bool {
-    // 中文字符范围
+/// 判断字符是否为中日韩文字或全角标点
+pub(crate) fn is_cjk_char(c: char) -> bool {
+    // 汉字范围
     (0x4E00..=0x9FFF).contains(&(c as u32)) ||  // CJK统一汉字
     (0x3400..=0x4DBF).contains(&(c as u32)) ||  // CJK扩展A
     (0x20000..=0x2A6DF).contains(&(c as u32)) || // CJK扩展B
@@ -58,406 +67,2550 @@ fn is_chinese_char(c: char) -> bool {
     (0x2B740..=0x2B81F).contains(&(c as u32)) || // CJK扩展D
     (0x2B820..=0x2CEAF).contains(&(c as u32)) || // CJK扩展E
     (0x2CEB0..=0x2EBEF).contains(&(c as u32)) || // CJK扩展F
+    (0x3040..=0x309F).contains(&(c as u32)) ||   // 日文平假名
+    (0x30A0..=0x30FF).contains(&(c as u32)) ||   // 日文片假名
+    (0xAC00..=0xD7AF).contains(&(c as u32)) ||   // 韩文音节
     (0x3000..=0x303F).contains(&(c as u32)) ||   // CJK符号和标点
     (0xFF00..=0xFFEF).contains(&(c as u32))      // 全角ASCII、全角标点
 }
 
-#[cfg(target_os = "windows")]
-use base64::{Engine as _, engine::general_purpose};
-
 #[cfg(target_os = "windows")]
 use windows::{
     core::*,
+    Globalization::Language,
     Graphics::Imaging::BitmapDecoder,
     Media::Ocr::OcrEngine,
-    Storage::{FileAccessMode, StorageFile},
+    Storage::{
+        FileAccessMode, StorageFile,
+        Streams::{DataWriter, IRandomAccessStream, InMemoryRandomAccessStream},
+    },
 };
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct OcrResult {
     pub text: String,
     pub success: bool,
     pub error_message: Option<String>,
+    // 失败原因的分类，成功时为 None；error_message 仍给出具体信息
+    #[serde(default)]
+    pub error_code: Option<OcrError>,
+    #[serde(default)]
+    pub lines: Vec<OcrLine>, // 每一行的识别结果及位置
+    // 使用 region_polygon 或 region 时，从识别所用图片的归一化坐标映射回原图归一化坐标的单应矩阵（按行排列的 3x3 矩阵）
+    #[serde(default)]
+    pub applied_transform: Option<[f64; 9]>,
+    // 整体识别置信度（0..1），为各行置信度按文字长度的加权平均；没有任何一行提供置信度时为 None
+    #[serde(default)]
+    pub confidence: Option<f32>,
+    // 使用 auto_orient 时识别前将图片顺时针旋转的角度（0、90、180 或 270），未使用时为 None
+    // lines 的坐标相对于旋转后的图片；旋转不为 0 时 applied_transform 已包含旋转，可以直接映射回原图
+    #[serde(default)]
+    pub applied_rotation: Option<u16>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct OcrRequest {
-    pub image_data: String, // base64 encoded image data
-    pub languages: Option<Vec<String>>, // OCR 识别语言
+/// 失败原因的分类，序列化为稳定的字符串（例如 "binary_not_found"），前端可以据此区分错误并做本地化
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OcrError {
+    // 请求参数有误，例如 image_data 和 image_path 都提供或都未提供、区域四边形无效
+    InvalidRequest,
+    // 图片文件不存在或无法读取
+    ImageUnreadable,
+    // base64 或图片格式无法解码
+    DecodeFailed,
+    // 找不到OCR程序（macOS 的 ocr 或 Linux 的 tesseract）
+    BinaryNotFound,
+    // 请求的语言不支持或语言包未安装
+    UnsupportedLanguage,
+    // OCR 引擎执行失败
+    EngineFailure,
+    // 识别超时
+    Timeout,
+    // 任务被 cancel_ocr 取消
+    Cancelled,
+    // 当前平台不支持系统OCR
+    UnsupportedPlatform,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct SupportedLanguagesResult {
-    pub languages: Vec<String>,
-    pub success: bool,
-    pub error_message: Option<String>,
+/// 识别出的一行文字
+/// 坐标以图片左上角为原点，按图片宽高归一化到 0..1，前端按实际显示尺寸缩放即可
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OcrLine {
+    pub text: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    // 识别置信度（0..1）：macOS 为 Vision 首选候选的置信度，Linux 为 tesseract 各单词置信度的平均值；
+    // Windows OCR 不提供置信度，始终为 None
+    pub confidence: Option<f32>,
+    // 该行在阅读顺序中的位置是否可靠（0..1），由 OcrResult::from_lines 根据分栏结构计算，规则见 layout.rs
+    #[serde(default)]
+    pub reading_order_confidence: f32,
 }
 
-#[command]
-pub async fn extract_text_with_system_ocr(request: OcrRequest) -> OcrResult {
-    #[cfg(target_os = "macos")]
-    {
-        // 在macOS上使用系统OCR
-        extract_text_macos(request).await
-    }
-    
-    #[cfg(target_os = "windows")]
-    {
-        // 在Windows上使用系统OCR
-        extract_text_windows(request).await
+impl OcrResult {
+    /// 由识别出的各行构造成功结果，text 为各行以换行符连接
+    /// 同时计算每一行的阅读顺序置信度和整体识别置信度
+    pub fn from_lines(mut lines: Vec<OcrLine>) -> Self {
+        let confidences = layout::reading_order_confidence(&lines);
+        for (line, confidence) in lines.iter_mut().zip(confidences) {
+            line.reading_order_confidence = confidence;
+        }
+        
+        let text = lines
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let confidence = overall_confidence(&lines);
+        OcrResult {
+            text,
+            success: true,
+            error_message: None,
+            error_code: None,
+            lines,
+            applied_transform: None,
+            confidence,
+            applied_rotation: None,
+        }
     }
-    
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    {
-        // 非macOS和非Windows平台返回错误
+
+    /// 构造失败结果
+    pub fn failure(code: OcrError, message: String) -> Self {
         OcrResult {
             text: String::new(),
             success: false,
-            error_message: Some("System OCR is only available on macOS and Windows".to_string()),
+            error_message: Some(message),
+            error_code: Some(code),
+            lines: vec![],
+            applied_transform: None,
+            confidence: None,
+            applied_rotation: None,
         }
     }
 }
 
-#[command]
-pub async fn get_supported_recognition_languages() -> SupportedLanguagesResult {
-    #[cfg(target_os = "macos")]
-    {
-        // 在macOS上获取支持的语言
-        get_supported_languages_macos().await
+/// 各行识别置信度按文字长度的加权平均，长的行对整体结果的影响更大；不提供置信度的行不参与计算
+fn overall_confidence(lines: &[OcrLine]) -> Option<f32> {
+    let (sum, weight) = lines
+        .iter()
+        .filter_map(|line| line.confidence.map(|confidence| (confidence, line.text.chars().count().max(1))))
+        .fold((0.0f64, 0usize), |(sum, weight), (confidence, length)| {
+            (sum + confidence as f64 * length as f64, weight + length)
+        });
+    if weight == 0 {
+        None
+    } else {
+        Some((sum / weight as f64) as f32)
     }
-    
-    #[cfg(target_os = "windows")]
-    {
-        // 在Windows上获取支持的语言
-        get_supported_languages_windows().await
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct OcrRequest {
+    // base64 encoded image data，也可以是 data URL（data:image/png;base64,...）或 URL 安全的 base64
+    // image_data 和 image_path 必须且只能提供一个
+    pub image_data: Option<String>,
+    // 磁盘上的图片文件路径；大图片用路径可以避免 base64 编码和经过 IPC 传输
+    pub image_path: Option<String>,
+    pub languages: Option<Vec<String>>, // OCR 识别语言
+    // 只识别图片中的一个四边形区域（依次为左上、右上、右下、左下，归一化坐标），识别前先透视校正为矩形
+    pub region_polygon: Option<[Point; 4]>,
+    // 只识别图片中的一个矩形区域（归一化坐标），识别前先裁剪；不能与 region_polygon 同时指定
+    pub region: Option<Region>,
+    // 识别超时时间（毫秒），超时后终止识别并返回失败；未指定时不限时
+    pub timeout_ms: Option<u64>,
+    // 识别结果的后处理选项，未指定时不做任何处理
+    pub post_process: Option<PostProcessOptions>,
+    // 识别精度（"fast" 或 "accurate"），仅 macOS 支持，未指定时使用 accurate；其他平台忽略
+    // 其他取值在反序列化请求时即报错
+    pub recognition_level: Option<RecognitionLevel>,
+    // 任务ID，用于 cancel_ocr 取消识别；批量识别时多张图片可以使用同一个任务ID，一次全部取消
+    pub job_id: Option<String>,
+    // 识别前的图片预处理，未指定或为 false 时图片保持不变；在 region_polygon 校正或 region 裁剪之后进行
+    // 可以传 true 使用默认的灰度化和对比度增强，也可以传 PreprocessOptions 对象
+    pub preprocess: Option<Preprocess>,
+    // 为 true 时自动判断页面方向：依次按 0、90、180、270 度识别，使用结果最好的方向，
+    // 所用的角度见 OcrResult 的 applied_rotation；最多需要识别四次，耗时相应增加
+    pub auto_orient: Option<bool>,
+}
+
+/// 预处理开关或具体的预处理选项
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Preprocess {
+    Enabled(bool),
+    Options(PreprocessOptions),
+}
+
+impl Preprocess {
+    /// 实际要执行的预处理选项，关闭时返回 None
+    fn options(&self) -> Option<PreprocessOptions> {
+        match self {
+            Preprocess::Enabled(true) => Some(PreprocessOptions::standard()),
+            Preprocess::Enabled(false) => None,
+            Preprocess::Options(options) => Some(options.clone()),
+        }
     }
-    
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    {
-        // 非macOS和非Windows平台返回错误
-        SupportedLanguagesResult {
-            languages: vec![],
-            success: false,
-            error_message: Some("System OCR is only available on macOS and Windows".to_string()),
+}
+
+/// 识别前的图片预处理选项，各平台行为一致，依次做灰度化、对比度调整和二值化
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PreprocessOptions {
+    // 转为灰度图，去除偏色
+    #[serde(default)]
+    pub grayscale: bool,
+    // 对比度调整的百分比，正数增强、负数减弱，例如 30.0
+    pub contrast: Option<f32>,
+    // 二值化阈值（0~255），亮度不低于阈值的像素变为白色，其余变为黑色
+    pub threshold: Option<u8>,
+}
+
+// preprocess 为 true 时的对比度增强百分比
+const STANDARD_CONTRAST: f32 = 30.0;
+
+impl PreprocessOptions {
+    /// 默认预处理：灰度化并增强对比度，适合光线不均的手机拍照文档；不做二值化，以免丢失浅色笔画
+    fn standard() -> Self {
+        PreprocessOptions {
+            grayscale: true,
+            contrast: Some(STANDARD_CONTRAST),
+            threshold: None,
         }
     }
 }
 
-#[cfg(target_os = "windows")]
-async fn extract_text_windows(request: OcrRequest) -> OcrResult {
-    use std::io::Write;
-    use std::fs::File;
-    use std::env::temp_dir;
-    use windows::{
-        Graphics::Imaging::BitmapDecoder,
-        Media::Ocr::OcrEngine,
-        Storage::{FileAccessMode, StorageFile},
-    };
-    use futures::executor::block_on;
-    
-    // 解码base64图像数据
-    let image_data = match general_purpose::STANDARD.decode(&request.image_data) {
-        Ok(data) => data,
-        Err(e) => {
-            return OcrResult {
-                text: String::new(),
-                success: false,
-                error_message: Some(format!("Failed to decode base64 image data: {}", e)),
-            };
+/// 识别精度：fast 速度快，适合实时预览；accurate 识别质量更好，适合最终提取
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecognitionLevel {
+    Fast,
+    Accurate,
+}
+
+impl RecognitionLevel {
+    /// 传给 OCR 程序的 --level 参数
+    #[cfg(target_os = "macos")]
+    fn as_arg(self) -> &'static str {
+        match self {
+            RecognitionLevel::Fast => "fast",
+            RecognitionLevel::Accurate => "accurate",
         }
-    };
-    
-    // 创建临时文件
-    let mut temp_file_path = temp_dir();
-    temp_file_path.push(format!("ocr_temp_{}.png", uuid::Uuid::new_v4()));
-    
-    // 将图像数据写入临时文件
-    let mut temp_file = match File::create(&temp_file_path) {
-        Ok(file) => file,
-        Err(e) => {
-            return OcrResult {
-                text: String::new(),
-                success: false,
-                error_message: Some(format!("Failed to create temporary file: {}", e)),
-            };
+    }
+}
+
+/// 识别结果的后处理选项，各平台行为一致，默认全部关闭
+/// 按以下顺序处理：collapse_cjk_spaces、normalize_fullwidth（作用于每一行），
+/// 然后 merge_paragraphs、dehyphenate、collapse_blank_lines（只作用于 text）
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PostProcessOptions {
+    // 去除中日韩文字之间的空格
+    #[serde(default)]
+    pub collapse_cjk_spaces: bool,
+    // 将折行的各行合并为段落，只影响 text，lines 保持不变；规则见 layout::merge_paragraphs
+    #[serde(default)]
+    pub merge_paragraphs: bool,
+    // 去掉行尾断词的连字符并接回单词，规则见 text::dehyphenate；只影响 text
+    #[serde(default)]
+    pub dehyphenate: bool,
+    // 将连续的空行合并为一个空行；只影响 text
+    #[serde(default)]
+    pub collapse_blank_lines: bool,
+    // 将全角字母、数字、标点和全角空格转换为半角
+    #[serde(default)]
+    pub normalize_fullwidth: bool,
+}
+
+/// 待识别的图像：内存中的图像数据，或者磁盘上的图片文件
+enum ImageSource {
+    Data(Vec<u8>),
+    Path(std::path::PathBuf),
+}
+
+impl ImageSource {
+    /// 计算识别结果的缓存键；图片文件读取失败时返回 None，不使用缓存
+    fn cache_key(&self, request: &OcrRequest) -> Option<CacheKey> {
+        match self {
+            ImageSource::Data(image_data) => {
+                Some(CacheKey::new(image_data, &request.languages, request.recognition_level))
+            }
+            ImageSource::Path(path) => CacheKey::from_file(path, &request.languages, request.recognition_level).ok(),
         }
-    };
+    }
     
-    if let Err(e) = temp_file.write_all(&image_data) {
-        return OcrResult {
-            text: String::new(),
-            success: false,
-            error_message: Some(format!("Failed to write image data to temporary file: {}", e)),
-        };
+    /// 读取图像数据
+    fn into_bytes(self) -> std::result::Result<Vec<u8>, String> {
+        match self {
+            ImageSource::Data(image_data) => Ok(image_data),
+            ImageSource::Path(path) => std::fs::read(&path)
+                .map_err(|e| format!("Failed to read image file {:?}: {}", path, e)),
+        }
     }
     
-    // 执行OCR识别
-    let result = block_on(async {
-        // 获取文件路径
-        let file_path = temp_file_path.to_str().unwrap_or("");
-        if file_path.is_empty() {
-            return Err("Failed to get temporary file path".to_string());
+    /// 转换为OCR后端可读取的图片文件：图片文件直接使用，内存中的图像数据写入临时文件
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    fn into_file(self) -> std::result::Result<ImageFile, String> {
+        match self {
+            ImageSource::Data(image_data) => TempFile::write(&image_data).map(ImageFile::Temp),
+            ImageSource::Path(path) => Ok(ImageFile::Existing(path)),
         }
-        
-        // 使用Windows OCR API
-        let file = StorageFile::GetFileFromPathAsync(&HSTRING::from(file_path))
-            .map_err(|e| format!("Failed to get storage file: {:?}", e))?
-            .join()
-            .map_err(|e| format!("Failed to join storage file operation: {:?}", e))?;
-            
-        let stream = file.OpenAsync(FileAccessMode::Read)
-            .map_err(|e| format!("Failed to open file stream: {:?}", e))?
-            .join()
-            .map_err(|e| format!("Failed to join file stream operation: {:?}", e))?;
+    }
+}
 
-        let decoder = BitmapDecoder::CreateAsync(&stream)
-            .map_err(|e| format!("Failed to create bitmap decoder: {:?}", e))?
-            .join()
-            .map_err(|e| format!("Failed to join bitmap decoder operation: {:?}", e))?;
-            
-        let bitmap = decoder.GetSoftwareBitmapAsync()
-            .map_err(|e| format!("Failed to get software bitmap: {:?}", e))?
-            .join()
-            .map_err(|e| format!("Failed to join software bitmap operation: {:?}", e))?;
+/// OCR后端读取的图片文件
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+enum ImageFile {
+    // 直接使用的图片文件，识别后保留
+    Existing(std::path::PathBuf),
+    // 由图像数据写入的临时文件，drop 时删除
+    Temp(TempFile),
+}
 
-        let engine = OcrEngine::TryCreateFromUserProfileLanguages()
-            .map_err(|e| format!("Failed to create OCR engine: {:?}", e))?;
-            
-        let ocr_result = engine.RecognizeAsync(&bitmap)
-            .map_err(|e| format!("Failed to recognize text: {:?}", e))?
-            .join()
-            .map_err(|e| format!("Failed to join OCR operation: {:?}", e))?;
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+impl ImageFile {
+    fn path(&self) -> &std::path::Path {
+        match self {
+            ImageFile::Existing(path) => path,
+            ImageFile::Temp(temp_file) => &temp_file.path,
+        }
+    }
+}
 
-        // 使用 Lines() 方法获取每行文字，并用换行符连接
-        let lines = ocr_result.Lines()
-            .map_err(|e| format!("Failed to get OCR result lines: {:?}", e))?;
+/// 临时图片文件，drop 时自动删除，无论识别从哪个分支返回都不会遗留在临时目录中
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+struct TempFile {
+    path: std::path::PathBuf,
+}
+
+/// 临时文件所在的目录：环境变量 OCR_TEMP_DIR 指定的目录（不存在时创建），未设置时使用系统临时目录
+/// 用于系统临时目录不可写或会被清理的环境
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn ocr_temp_dir() -> std::result::Result<std::path::PathBuf, String> {
+    match std::env::var_os("OCR_TEMP_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => {
+            let dir = std::path::PathBuf::from(dir);
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create OCR_TEMP_DIR {:?}: {}", dir, e))?;
+            Ok(dir)
+        }
+        None => Ok(std::env::temp_dir()),
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+impl TempFile {
+    /// 将图像数据写入临时目录中的新文件，扩展名与图像数据的实际格式一致
+    fn write(image_data: &[u8]) -> std::result::Result<Self, String> {
+        use std::io::Write;
         
-        let text = lines.into_iter()
-            .map(|line| {
-                line.Text()
-                    .map(|hstring| hstring.to_string())
-                    .unwrap_or_default()
-            })
-            .collect::<Vec<String>>()
-            .join("\n");
+        let extension = image_ops::detect_image_format(image_data)
+            .and_then(|format| format.extensions_str().first().copied())
+            .ok_or_else(|| UNSUPPORTED_IMAGE_FORMAT.to_string())?;
+        let mut path = ocr_temp_dir()?;
+        path.push(format!("ocr_temp_{}.{}", uuid::Uuid::new_v4(), extension));
         
-        // 去除中文字符之间的空格
-        let text = remove_chinese_spaces(&text);
-        Ok(text)
-    });
-    
-    // 清理临时文件
-    let _ = std::fs::remove_file(&temp_file_path);
-    
-    match result {
-        Ok(text) => OcrResult {
-            text,
-            success: true,
-            error_message: None,
-        },
-        Err(e) => OcrResult {
-            text: String::new(),
-            success: false,
-            error_message: Some(e),
-        },
+        let mut file = std::fs::File::create(&path)
+            .map_err(|e| format!("Failed to create temporary file {:?}: {}", path, e))?;
+        // 文件已创建，之后写入失败时由 drop 删除
+        let temp_file = TempFile { path };
+        file.write_all(image_data)
+            .map_err(|e| format!("Failed to write image data to temporary file: {}", e))?;
+        
+        Ok(temp_file)
     }
 }
 
-#[cfg(target_os = "windows")]
-async fn get_supported_languages_windows() -> SupportedLanguagesResult {
-    use windows::{
-        Media::Ocr::OcrEngine,
-    };
-    
-    // Windows OCR使用系统默认语言，不需要显式指定语言
-    // 返回一个默认语言列表
-    SupportedLanguagesResult {
-        languages: vec!["en-US".to_string(), "zh-CN".to_string()], // 示例语言
-        success: true,
-        error_message: None,
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
     }
 }
 
-#[cfg(target_os = "macos")]
-async fn get_supported_languages_macos() -> SupportedLanguagesResult {
-    // 获取OCR可执行文件路径
-    // 首先尝试从环境变量获取（由build.rs设置）
-    let ocr_executable_path = if let Ok(path) = std::env::var("OCR_EXECUTABLE_PATH") {
-        std::path::PathBuf::from(path)
-    } else {
-        // 如果环境变量不存在，尝试在当前可执行文件目录查找
-        let exe_path = std::env::current_exe().unwrap_or_else(|_| std::path::PathBuf::from("./"));
-        let exe_dir = exe_path.parent().unwrap_or_else(|| std::path::Path::new("."));
-        exe_dir.join("ocr")
-    };
+/// 归一化坐标（0..1，以图片左上角为原点）中的一个点
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// 归一化坐标中的矩形区域，(x, y) 为左上角
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Region {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SupportedLanguagesResult {
+    pub languages: Vec<String>,
+    pub success: bool,
+    pub error_message: Option<String>,
+    #[serde(default)]
+    pub error_code: Option<OcrError>,
+}
+
+impl SupportedLanguagesResult {
+    /// 构造成功结果
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    fn from_languages(languages: Vec<String>) -> Self {
+        SupportedLanguagesResult {
+            languages,
+            success: true,
+            error_message: None,
+            error_code: None,
+        }
+    }
     
-    // 检查OCR可执行文件是否存在
-    if !ocr_executable_path.exists() {
-        return SupportedLanguagesResult {
+    /// 构造失败结果
+    fn failure(code: OcrError, message: String) -> Self {
+        SupportedLanguagesResult {
             languages: vec![],
             success: false,
-            error_message: Some(format!("OCR executable not found at: {:?}", ocr_executable_path)),
-        };
+            error_message: Some(message),
+            error_code: Some(code),
+        }
     }
-    
-    // 执行OCR程序获取支持的语言
-    let output = Command::new(&ocr_executable_path)
-        .output();
-    
-    match output {
-        Ok(output) => {
-            if output.status.success() {
-                let output_str = String::from_utf8_lossy(&output.stdout);
-                let lines: Vec<&str> = output_str.lines().collect();
-                
-                // 查找语言列表的开始和结束标记
-                let start_index = lines.iter().position(|&line| line == "SUPPORTED_LANGUAGES_START");
-                let end_index = lines.iter().position(|&line| line == "SUPPORTED_LANGUAGES_END");
-                
-                if let (Some(start), Some(end)) = (start_index, end_index) {
-                    // 提取语言列表
-                    let languages: Vec<String> = lines[start+1..end]
-                        .iter()
-                        .map(|s| s.to_string())
-                        .collect();
-                    
-                    SupportedLanguagesResult {
-                        languages,
-                        success: true,
-                        error_message: None,
-                    }
-                } else {
-                    SupportedLanguagesResult {
-                        languages: vec![],
-                        success: false,
-                        error_message: Some("Failed to parse supported languages from OCR output".to_string()),
-                    }
-                }
-            } else {
-                let error = String::from_utf8_lossy(&output.stderr);
-                SupportedLanguagesResult {
-                    languages: vec![],
-                    success: false,
-                    error_message: Some(format!("Failed to get supported languages: {}", error)),
-                }
-            }
+}
+
+// 任务被取消时的错误信息
+const OCR_CANCELLED: &str = "OCR cancelled";
+
+/// 任务表中的一项：取消标志，以及尚未 drop 的 OcrJob 数量
+#[derive(Default)]
+struct JobEntry {
+    cancelled: Arc<AtomicBool>,
+    registrations: usize,
+}
+
+lazy_static::lazy_static! {
+    // 正在进行的OCR任务：任务ID -> 取消标志和注册次数
+    static ref OCR_JOBS: Mutex<HashMap<String, JobEntry>> = Mutex::new(HashMap::new());
+}
+
+/// 已注册的OCR任务，drop 时从任务表中移除
+/// 同一个任务ID可以注册多次（例如批量识别中的多张图片），共用一个取消标志，最后一个 drop 时才移除
+/// 以注册次数而不是取消标志的引用计数判断，识别过程中另外持有的取消标志不会使任务留在表中
+struct OcrJob {
+    job_id: String,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl OcrJob {
+    fn register(job_id: &str) -> Self {
+        let mut jobs = OCR_JOBS.lock().unwrap();
+        let entry = jobs.entry(job_id.to_string()).or_default();
+        entry.registrations += 1;
+        OcrJob {
+            job_id: job_id.to_string(),
+            cancelled: entry.cancelled.clone(),
         }
-        Err(e) => {
-            SupportedLanguagesResult {
-                languages: vec![],
-                success: false,
-                error_message: Some(format!("Failed to execute OCR to get supported languages: {}", e)),
+    }
+}
+
+impl Drop for OcrJob {
+    fn drop(&mut self) {
+        let mut jobs = OCR_JOBS.lock().unwrap();
+        if let Some(entry) = jobs.get_mut(&self.job_id) {
+            entry.registrations -= 1;
+            if entry.registrations == 0 {
+                jobs.remove(&self.job_id);
             }
         }
     }
 }
 
-#[cfg(target_os = "macos")]
-async fn extract_text_macos(request: OcrRequest) -> OcrResult {
-    use std::io::Write;
-    use std::fs::File;
-    use std::env::temp_dir;
-    use base64::{Engine as _, engine::general_purpose};
-    
-    // 解码base64图像数据
-    let image_data = match general_purpose::STANDARD.decode(&request.image_data) {
-        Ok(data) => data,
-        Err(e) => {
-            return OcrResult {
-                text: String::new(),
-                success: false,
-                error_message: Some(format!("Failed to decode base64 image data: {}", e)),
-            };
-        }
-    };
-    
-    // 创建临时文件
-    let mut temp_file_path = temp_dir();
-    temp_file_path.push(format!("ocr_temp_{}.png", uuid::Uuid::new_v4()));
-    
-    // 将图像数据写入临时文件
-    let mut temp_file = match File::create(&temp_file_path) {
-        Ok(file) => file,
-        Err(e) => {
-            return OcrResult {
-                text: String::new(),
-                success: false,
-                error_message: Some(format!("Failed to create temporary file: {}", e)),
-            };
+/// 请求所属任务的取消标志，未指定 job_id 时为 None
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+fn cancel_flag(request: &OcrRequest) -> Option<Arc<AtomicBool>> {
+    let job_id = request.job_id.as_ref()?;
+    OCR_JOBS.lock().unwrap().get(job_id).map(|entry| entry.cancelled.clone())
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+fn is_cancelled(cancelled: Option<&AtomicBool>) -> bool {
+    cancelled.is_some_and(|cancelled| cancelled.load(Ordering::SeqCst))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CancelOcrResult {
+    pub success: bool,
+    pub error_message: Option<String>,
+}
+
+/// 取消正在进行的OCR任务：终止正在运行的OCR程序，尚未开始识别的图片直接返回取消结果
+/// 被取消的请求返回 error_code 为 cancelled 的失败结果
+#[command]
+pub async fn cancel_ocr(job_id: String) -> CancelOcrResult {
+    match OCR_JOBS.lock().unwrap().get(&job_id) {
+        Some(entry) => {
+            entry.cancelled.store(true, Ordering::SeqCst);
+            CancelOcrResult {
+                success: true,
+                error_message: None,
+            }
         }
-    };
-    
-    if let Err(e) = temp_file.write_all(&image_data) {
-        return OcrResult {
-            text: String::new(),
+        None => CancelOcrResult {
             success: false,
-            error_message: Some(format!("Failed to write image data to temporary file: {}", e)),
-        };
+            error_message: Some(format!("No active OCR job with id {}", job_id)),
+        },
     }
-    
-    // 获取OCR可执行文件路径
-    // 首先尝试从环境变量获取（由build.rs设置）
-    let ocr_executable_path = if let Ok(path) = std::env::var("OCR_EXECUTABLE_PATH") {
-        std::path::PathBuf::from(path)
-    } else {
-        // 如果环境变量不存在，尝试在当前可执行文件目录查找
-        let exe_path = std::env::current_exe().unwrap_or_else(|_| std::path::PathBuf::from("./"));
-        let exe_dir = exe_path.parent().unwrap_or_else(|| std::path::Path::new("."));
-        exe_dir.join("ocr")
+}
+
+#[command]
+pub async fn extract_text_with_system_ocr(request: OcrRequest) -> OcrResult {
+    let _job = request.job_id.as_deref().map(OcrJob::register);
+    recognize_request(&request).await
+}
+
+/// 识别一个请求：准备图像、识别（按需判断方向）并做后处理
+async fn recognize_request(request: &OcrRequest) -> OcrResult {
+    // 解码图像数据，并按请求对图像做几何变换
+    let (image, applied_transform) = match prepare_image(request) {
+        Ok(prepared) => prepared,
+        Err((code, e)) => return OcrResult::failure(code, e),
     };
     
-    // 检查OCR可执行文件是否存在
-    if !ocr_executable_path.exists() {
-        let _ = std::fs::remove_file(&temp_file_path);
-        return OcrResult {
-            text: String::new(),
-            success: false,
-            error_message: Some(format!("OCR executable not found at: {:?}", ocr_executable_path)),
+    if request.auto_orient.unwrap_or(false) {
+        return match recognize_oriented(image, request).await {
+            Ok((result, rotation, rotation_transform)) => {
+                let mut result = post_process(result, &request.post_process);
+                if result.success {
+                    result.applied_transform = compose_transform(applied_transform, rotation_transform);
+                    result.applied_rotation = Some(rotation);
+                }
+                result
+            }
+            Err((code, e)) => OcrResult::failure(code, e),
         };
     }
     
-    // 构建命令参数
-    let mut cmd = Command::new(&ocr_executable_path);
-    cmd.arg(&temp_file_path);
-    
-    // 如果提供了语言选项，则添加语言参数
-    if let Some(languages) = &request.languages {
-        if !languages.is_empty() {
-            let languages_str = languages.join(",");
-            cmd.arg(languages_str);
+    let result = recognize_cached(image, request).await;
+    let mut result = post_process(result, &request.post_process);
+    if result.success {
+        result.applied_transform = applied_transform;
+    }
+    result
+}
+
+/// ocr-page-result 事件的内容：流式识别中每识别完一页发送一次
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OcrPageResultEvent {
+    pub job_id: String,
+    pub index: usize, // 该页在请求中的下标
+    pub result: OcrResult,
+}
+
+/// ocr-stream-done 事件的内容：流式识别结束（全部完成或被取消）时发送一次
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OcrStreamDoneEvent {
+    pub job_id: String,
+    pub completed: usize, // 已发送 ocr-page-result 的页数
+    pub cancelled: bool,
+}
+
+/// 流式识别多页：立即返回，之后按请求的顺序逐页识别，每识别完一页发送 ocr-page-result 事件，
+/// 最后发送 ocr-stream-done 事件；与 extract_text_batch 不同，前端不必等待全部完成即可显示已识别的页面
+/// 所有请求都归入 job_id 任务（覆盖请求中的 job_id）；cancel_ocr(job_id) 后中止正在进行的识别，
+/// 不再发送 ocr-page-result，只发送 cancelled 为 true 的 ocr-stream-done
+#[command]
+pub async fn extract_text_stream(app_handle: tauri::AppHandle, requests: Vec<OcrRequest>, job_id: String) {
+    // 在返回前注册任务，前端收到返回后立即 cancel_ocr 也能取消
+    let job = OcrJob::register(&job_id);
+    
+    tauri::async_runtime::spawn(async move {
+        let mut completed = 0;
+        for (index, mut request) in requests.into_iter().enumerate() {
+            if job.cancelled.load(Ordering::SeqCst) {
+                break;
+            }
+            request.job_id = Some(job_id.clone());
+            let result = recognize_request(&request).await;
+            if job.cancelled.load(Ordering::SeqCst) {
+                break;
+            }
+            let _ = app_handle.emit("ocr-page-result", OcrPageResultEvent {
+                job_id: job_id.clone(),
+                index,
+                result,
+            });
+            completed += 1;
+        }
+        
+        let _ = app_handle.emit("ocr-stream-done", OcrStreamDoneEvent {
+            job_id,
+            completed,
+            cancelled: job.cancelled.load(Ordering::SeqCst),
+        });
+    });
+}
+
+/// 渲染PDF文档的一页并识别，省去前端栅格化整页图片并经过 IPC 传输图像数据
+/// page 从 1 开始，超出文档页数时返回 InvalidRequest；dpi 为渲染分辨率，未指定时为 300
+/// 渲染方式见 pdf_render::render_page
+#[command]
+pub async fn ocr_pdf_page(pdf_path: String, page: u32, dpi: Option<u32>, languages: Option<Vec<String>>) -> OcrResult {
+    // 渲染同样占用一个许可，识别前归还，识别时由 recognize_image 重新取得
+    let rendered = {
+        let _permit = ocr_limit::acquire().await;
+        let dpi = dpi.unwrap_or(pdf_render::DEFAULT_DPI);
+        run_blocking(move || pdf_render::render_page(&pdf_path, page, dpi)).await
+    };
+    let image_data = match rendered {
+        Ok(Ok(image_data)) => image_data,
+        Ok(Err((code, e))) => return OcrResult::failure(code, e),
+        Err(e) => return OcrResult::failure(OcrError::EngineFailure, e),
+    };
+    
+    let request = OcrRequest {
+        languages,
+        ..Default::default()
+    };
+    recognize_cached(ImageSource::Data(image_data), &request).await
+}
+
+/// 识别图像；相同的图像和识别参数直接使用缓存的结果
+async fn recognize_cached(image: ImageSource, request: &OcrRequest) -> OcrResult {
+    let cache_key = image.cache_key(request);
+    match cache_key.as_ref().and_then(ocr_cache::get) {
+        Some(result) => result,
+        None => {
+            let result = recognize_image(image, request).await;
+            if let Some(cache_key) = cache_key {
+                ocr_cache::insert(cache_key, &result);
+            }
+            result
+        }
+    }
+}
+
+// auto_orient 依次尝试的顺时针旋转角度
+const AUTO_ORIENT_ROTATIONS: [u16; 4] = [0, 90, 180, 270];
+// 某个方向的整体置信度达到该值时不再尝试其他方向
+const AUTO_ORIENT_CONFIDENT: f32 = 0.9;
+
+/// 按各个方向识别图像，返回得分最高的结果、所用的旋转角度，以及旋转后坐标映射回旋转前坐标的矩阵
+/// 得分为各行文字长度按置信度加权之和：方向错误时通常识别不出文字，或者识别出的文字置信度很低；
+/// Windows OCR 不提供置信度，相当于比较识别出的文字数量
+async fn recognize_oriented(
+    image: ImageSource,
+    request: &OcrRequest,
+) -> std::result::Result<(OcrResult, u16, [f64; 9]), (OcrError, String)> {
+    let image_data = image.into_bytes().map_err(|e| (OcrError::ImageUnreadable, e))?;
+    let decoded = image_ops::decode_image(&image_data).map_err(|e| (OcrError::DecodeFailed, e))?;
+    
+    let mut best: Option<(OcrResult, u16, [f64; 9], f64)> = None;
+    for rotation in AUTO_ORIENT_ROTATIONS {
+        let (rotated, transform) = image_ops::rotate(&decoded, rotation).map_err(|e| (OcrError::DecodeFailed, e))?;
+        let result = recognize_cached(ImageSource::Data(rotated), request).await;
+        // 识别失败（例如找不到OCR程序、被取消）时其他方向同样会失败，直接返回
+        if !result.success {
+            return Ok((result, rotation, transform));
+        }
+        
+        let score: f64 = result
+            .lines
+            .iter()
+            .map(|line| line.confidence.unwrap_or(1.0) as f64 * line.text.chars().count() as f64)
+            .sum();
+        let confident = result.confidence.is_some_and(|confidence| confidence >= AUTO_ORIENT_CONFIDENT);
+        if best.as_ref().map_or(true, |(_, _, _, best_score)| score > *best_score) {
+            best = Some((result, rotation, transform, score));
+        }
+        if confident {
+            break;
+        }
+    }
+    
+    best.map(|(result, rotation, transform, _)| (result, rotation, transform))
+        .ok_or_else(|| (OcrError::EngineFailure, "OCR produced no result for this image".to_string()))
+}
+
+/// 组合图像的几何变换与方向旋转；未旋转时保持原来的变换
+fn compose_transform(transform: Option<[f64; 9]>, rotation_transform: [f64; 9]) -> Option<[f64; 9]> {
+    if rotation_transform == [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0] {
+        return transform;
+    }
+    Some(match transform {
+        Some(transform) => image_ops::compose_homography(&transform, &rotation_transform),
+        None => rotation_transform,
+    })
+}
+
+/// 按请求的后处理选项处理识别出的每一行，并重新生成全文
+fn post_process(result: OcrResult, options: &Option<PostProcessOptions>) -> OcrResult {
+    let options = match options {
+        Some(options) if result.success => options,
+        _ => return result,
+    };
+    
+    let mut lines = result.lines;
+    for line in &mut lines {
+        if options.collapse_cjk_spaces {
+            line.text = collapse_cjk_spaces(&line.text);
+        }
+        // 在去除空格之后转换，全角标点两侧的空格仍按中日韩标点处理
+        if options.normalize_fullwidth {
+            line.text = text::normalize_fullwidth(&line.text);
+        }
+    }
+    
+    let mut result = OcrResult {
+        applied_transform: result.applied_transform,
+        ..OcrResult::from_lines(lines)
+    };
+    if options.merge_paragraphs {
+        result.text = layout::merge_paragraphs(&result.lines);
+    }
+    if options.dehyphenate {
+        result.text = text::dehyphenate(&result.text);
+    }
+    if options.collapse_blank_lines {
+        result.text = text::collapse_blank_lines(&result.text);
+    }
+    result
+}
+
+/// ocr-progress 事件的内容：批量识别中每完成一张图片（无论成功与否）发送一次
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OcrProgressEvent {
+    pub job_id: Option<String>, // 刚完成的图片所属的任务ID
+    pub index: usize,           // 刚完成的图片在请求中的下标
+    pub completed: usize,
+    pub total: usize,
+    pub last_page_success: bool,
+}
+
+/// 批量识别的进度，每完成一张图片向前端发送 ocr-progress 事件
+/// 不借用请求，可以交给阻塞线程池中等待OCR程序的线程边识别边报告
+struct BatchProgress {
+    app_handle: tauri::AppHandle,
+    // 各请求的 job_id，与请求按下标一一对应
+    job_ids: Vec<Option<String>>,
+    completed: AtomicUsize,
+}
+
+impl BatchProgress {
+    fn page_done(&self, index: usize, success: bool) {
+        let completed = self.completed.fetch_add(1, Ordering::SeqCst) + 1;
+        let _ = self.app_handle.emit("ocr-progress", OcrProgressEvent {
+            job_id: self.job_ids[index].clone(),
+            index,
+            completed,
+            total: self.job_ids.len(),
+            last_page_success: success,
+        });
+    }
+}
+
+/// 批量识别多张图片，结果与请求按下标一一对应
+/// 某一张图片失败时只在对应位置返回失败结果，不影响其他图片
+/// 识别过程中每完成一张图片向前端发送 ocr-progress 事件，内容见 OcrProgressEvent；
+/// 完成的顺序不一定与请求的顺序相同，命中缓存和参数有误的图片最先完成；失败的图片同样发送事件
+#[command]
+pub async fn extract_text_batch(app_handle: tauri::AppHandle, requests: Vec<OcrRequest>) -> Vec<OcrResult> {
+    // 各张图片在单独的任务中识别，任务需要持有请求
+    let requests = Arc::new(requests);
+    let _jobs: Vec<OcrJob> = requests
+        .iter()
+        .filter_map(|request| request.job_id.as_deref())
+        .map(OcrJob::register)
+        .collect();
+    let progress = Arc::new(BatchProgress {
+        app_handle,
+        job_ids: requests.iter().map(|request| request.job_id.clone()).collect(),
+        completed: AtomicUsize::new(0),
+    });
+    let mut results: Vec<Option<OcrResult>> = Vec::with_capacity(requests.len());
+    let mut transforms: Vec<Option<[f64; 9]>> = vec![None; requests.len()];
+    let mut cache_keys: Vec<Option<CacheKey>> = vec![None; requests.len()];
+    let mut images: Vec<(usize, ImageSource)> = Vec::new();
+    let mut recognized: Vec<(usize, OcrResult)> = Vec::new();
+    
+    for (index, request) in requests.iter().enumerate() {
+        results.push(None);
+        // 解码或变换某一张图片时发生 panic（例如损坏的图片触发解码库的断言）只影响这一张，仍然报告进度并继续处理其他图片
+        let prepared = std::panic::catch_unwind(|| prepare_image(request)).unwrap_or_else(|_| {
+            Err((OcrError::DecodeFailed, "Image processing failed unexpectedly".to_string()))
+        });
+        match prepared {
+            // 需要判断方向的图片要按多个方向识别，不能合并到批量识别中
+            Ok((image, transform)) if request.auto_orient.unwrap_or(false) => {
+                let page_requests = requests.clone();
+                let result = recognize_page(async move {
+                    let request = &page_requests[index];
+                    match recognize_oriented(image, request).await {
+                        Ok((result, rotation, rotation_transform)) => {
+                            let mut result = post_process(result, &request.post_process);
+                            if result.success {
+                                result.applied_transform = compose_transform(transform, rotation_transform);
+                                result.applied_rotation = Some(rotation);
+                            }
+                            result
+                        }
+                        Err((code, e)) => OcrResult::failure(code, e),
+                    }
+                })
+                .await;
+                progress.page_done(index, result.success);
+                results[index] = Some(result);
+            }
+            Ok((image, transform)) => {
+                transforms[index] = transform;
+                // 命中缓存的图片不再识别
+                cache_keys[index] = image.cache_key(request);
+                match cache_keys[index].as_ref().and_then(ocr_cache::get) {
+                    Some(result) => {
+                        progress.page_done(index, result.success);
+                        recognized.push((index, result));
+                    }
+                    None => images.push((index, image)),
+                }
+            }
+            Err((code, e)) => {
+                results[index] = Some(OcrResult::failure(code, e));
+                progress.page_done(index, false);
+            }
+        }
+    }
+    
+    for (index, result) in recognize_batch(images, &requests, &progress).await {
+        if let Some(cache_key) = cache_keys[index].take() {
+            ocr_cache::insert(cache_key, &result);
+        }
+        recognized.push((index, result));
+    }
+    
+    for (index, result) in recognized {
+        let mut result = guard_page(|| post_process(result, &requests[index].post_process));
+        if result.success {
+            result.applied_transform = transforms[index];
+        }
+        results[index] = Some(result);
+    }
+    
+    results
+        .into_iter()
+        .map(|result| {
+            result.unwrap_or_else(|| {
+                OcrResult::failure(OcrError::EngineFailure, "OCR produced no result for this image".to_string())
+            })
+        })
+        .collect()
+}
+
+// 解码 image_data 用的 base64 引擎，末尾的 = 填充可有可无：前端各种编码方式生成的数据不一定带填充
+const BASE64_STANDARD: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    general_purpose::PAD.with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+const BASE64_URL_SAFE: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::URL_SAFE,
+    general_purpose::PAD.with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// 解码 image_data：支持普通的 base64、canvas.toDataURL 生成的 data URL（data:image/png;base64,...），
+/// 以及 URL 安全的 base64（使用 - 和 _）；带不带 = 填充都可以
+fn decode_image_data(image_data: &str) -> std::result::Result<Vec<u8>, String> {
+    let mut encoded = image_data.trim();
+    if let Some(data_url) = encoded.strip_prefix("data:") {
+        let (header, data) = data_url
+            .split_once(',')
+            .ok_or_else(|| "Invalid data URL: missing ',' before the image data".to_string())?;
+        let mime = header.split(';').next().unwrap_or_default();
+        if !mime.starts_with("image/") {
+            return Err(format!("Data URL has MIME type {:?}, expected an image type", mime));
+        }
+        if !header.split(';').any(|parameter| parameter == "base64") {
+            return Err("Data URL must be base64-encoded".to_string());
+        }
+        encoded = data;
+    }
+    
+    BASE64_STANDARD
+        .decode(encoded)
+        .or_else(|e| BASE64_URL_SAFE.decode(encoded).map_err(|_| e))
+        .map_err(|e| format!("Failed to decode base64 image data: {}", e))
+}
+
+// 图像数据不是OCR后端支持的格式时的错误信息
+const UNSUPPORTED_IMAGE_FORMAT: &str = "Unsupported image format: expected PNG, JPEG, BMP, TIFF or WebP";
+
+/// 准备好的待识别图像，以及对图像所做的几何变换
+type PreparedImage = (ImageSource, Option<[f64; 9]>);
+
+/// 取得请求中的图像：解码base64图像数据，或直接使用图片文件路径，指定了 preprocess 时再做预处理
+/// 指定了 region_polygon 时将该区域透视校正为矩形，指定了 region 时裁剪该区域，
+/// 同时返回所用的变换，供前端将识别结果的坐标映射回原图
+fn prepare_image(request: &OcrRequest) -> std::result::Result<PreparedImage, (OcrError, String)> {
+    let image = match (&request.image_data, &request.image_path) {
+        (Some(image_data), None) => {
+            let image_data = decode_image_data(image_data).map_err(|e| (OcrError::DecodeFailed, e))?;
+            // 在交给OCR后端之前检查实际格式，避免后端给出难以理解的错误
+            if image_ops::detect_image_format(&image_data).is_none() {
+                return Err((OcrError::DecodeFailed, UNSUPPORTED_IMAGE_FORMAT.to_string()));
+            }
+            ImageSource::Data(image_data)
+        }
+        (None, Some(image_path)) => {
+            ImageSource::Path(validate_image_path(image_path).map_err(|e| (OcrError::ImageUnreadable, e))?)
+        }
+        _ => {
+            return Err((
+                OcrError::InvalidRequest,
+                "Exactly one of image_data or image_path must be provided".to_string(),
+            ));
+        }
+    };
+    
+    let (image, transform) = match (&request.region_polygon, &request.region) {
+        (Some(polygon), None) => {
+            image_ops::validate_polygon(polygon).map_err(|e| (OcrError::InvalidRequest, e))?;
+            let image_data = image.into_bytes().map_err(|e| (OcrError::ImageUnreadable, e))?;
+            let (warped, transform) = image_ops::warp_quadrilateral(&image_data, polygon)
+                .map_err(|e| (OcrError::DecodeFailed, e))?;
+            (ImageSource::Data(warped), Some(transform))
+        }
+        (None, Some(region)) => {
+            image_ops::validate_region(region).map_err(|e| (OcrError::InvalidRequest, e))?;
+            let image_data = image.into_bytes().map_err(|e| (OcrError::ImageUnreadable, e))?;
+            let (cropped, transform) = image_ops::crop_region(&image_data, region)
+                .map_err(|e| (OcrError::DecodeFailed, e))?;
+            (ImageSource::Data(cropped), Some(transform))
+        }
+        (Some(_), Some(_)) => {
+            return Err((
+                OcrError::InvalidRequest,
+                "region and region_polygon cannot both be provided".to_string(),
+            ));
+        }
+        (None, None) => (image, None),
+    };
+
+    match request.preprocess.as_ref().and_then(Preprocess::options) {
+        Some(options) => {
+            let image_data = image.into_bytes().map_err(|e| (OcrError::ImageUnreadable, e))?;
+            let processed = image_ops::preprocess(&image_data, &options).map_err(|e| (OcrError::DecodeFailed, e))?;
+            Ok((ImageSource::Data(processed), transform))
+        }
+        None => Ok((image, transform)),
+    }
+}
+
+/// 检查图片文件存在且可读，避免把无效路径交给OCR后端后只得到含糊的错误
+fn validate_image_path(image_path: &str) -> std::result::Result<std::path::PathBuf, String> {
+    let path = std::path::PathBuf::from(image_path);
+    
+    let metadata = std::fs::metadata(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => format!("Image file not found: {}", image_path),
+        _ => format!("Cannot access image file {}: {}", image_path, e),
+    })?;
+    if !metadata.is_file() {
+        return Err(format!("Image path is not a file: {}", image_path));
+    }
+    
+    std::fs::File::open(&path)
+        .map_err(|e| format!("Image file is not readable: {}: {}", image_path, e))?;
+    
+    Ok(path)
+}
+
+/// 调用当前平台的OCR后端识别图像，同时进行的识别数量受 set_ocr_concurrency 限制
+async fn recognize_image(image: ImageSource, request: &OcrRequest) -> OcrResult {
+    let _permit = ocr_limit::acquire().await;
+    
+    #[cfg(target_os = "macos")]
+    {
+        // 在macOS上使用系统OCR
+        extract_text_macos(image, request).await
+    }
+    
+    #[cfg(target_os = "windows")]
+    {
+        // 在Windows上使用系统OCR
+        extract_text_windows(image, request).await
+    }
+    
+    #[cfg(target_os = "linux")]
+    {
+        // 在Linux上使用Tesseract
+        extract_text_linux(image, request).await
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        // 其他平台返回错误
+        let _ = (image, request);
+        OcrResult::failure(
+            OcrError::UnsupportedPlatform,
+            "System OCR is only available on macOS, Windows and Linux".to_string(),
+        )
+    }
+}
+
+/// 批量识别中的一张图片在处理时发生 panic 的结果
+fn page_panicked() -> OcrResult {
+    OcrResult::failure(OcrError::EngineFailure, "OCR failed unexpectedly for this image".to_string())
+}
+
+/// 在单独的任务中识别批量中的一张图片：发生 panic 时只有这一张失败，其他图片照常识别并报告进度
+async fn recognize_page(page: impl std::future::Future<Output = OcrResult> + Send + 'static) -> OcrResult {
+    tauri::async_runtime::spawn(page).await.unwrap_or_else(|_| page_panicked())
+}
+
+/// 同步处理批量中一张图片的结果，发生 panic 时只有这一张失败
+fn guard_page(process: impl FnOnce() -> OcrResult) -> OcrResult {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(process)).unwrap_or_else(|_| page_panicked())
+}
+
+/// 调用当前平台的OCR后端批量识别图像，images 中为 (请求下标, 图像数据)
+/// 整个批量识别只占用一个许可；每完成一张图片通过 progress 报告一次
+async fn recognize_batch(
+    images: Vec<(usize, ImageSource)>,
+    requests: &Arc<Vec<OcrRequest>>,
+    progress: &Arc<BatchProgress>,
+) -> Vec<(usize, OcrResult)> {
+    let _permit = ocr_limit::acquire().await;
+    
+    #[cfg(target_os = "macos")]
+    {
+        // 在macOS上只启动一次OCR程序识别所有图片
+        extract_text_batch_macos(images, requests, progress).await
+    }
+    
+    #[cfg(target_os = "windows")]
+    {
+        // 在Windows上复用同一个OCR引擎
+        extract_text_batch_windows(images, requests, progress).await
+    }
+    
+    #[cfg(target_os = "linux")]
+    {
+        // Tesseract 没有可复用的引擎，逐张识别
+        let mut results = Vec::with_capacity(images.len());
+        for (index, image) in images {
+            let page_requests = requests.clone();
+            let result = recognize_page(async move { extract_text_linux(image, &page_requests[index]).await }).await;
+            progress.page_done(index, result.success);
+            results.push((index, result));
+        }
+        results
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        // 其他平台返回错误
+        let _ = requests;
+        images
+            .into_iter()
+            .map(|(index, _)| {
+                let message = "System OCR is only available on macOS, Windows and Linux".to_string();
+                progress.page_done(index, false);
+                (index, OcrResult::failure(OcrError::UnsupportedPlatform, message))
+            })
+            .collect()
+    }
+}
+
+#[command]
+pub async fn get_supported_recognition_languages() -> SupportedLanguagesResult {
+    #[cfg(target_os = "macos")]
+    {
+        // 在macOS上获取支持的语言
+        get_supported_languages_macos().await
+    }
+    
+    #[cfg(target_os = "windows")]
+    {
+        // 在Windows上获取支持的语言
+        get_supported_languages_windows().await
+    }
+    
+    #[cfg(target_os = "linux")]
+    {
+        // 在Linux上获取Tesseract已安装的语言
+        get_supported_languages_linux().await
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        // 其他平台返回错误
+        SupportedLanguagesResult::failure(
+            OcrError::UnsupportedPlatform,
+            "System OCR is only available on macOS, Windows and Linux".to_string(),
+        )
+    }
+}
+
+/// 当前平台的OCR后端名称
+pub const OCR_BACKEND: &str = if cfg!(target_os = "macos") {
+    "Vision"
+} else if cfg!(target_os = "windows") {
+    "Windows.Media.Ocr"
+} else if cfg!(target_os = "linux") {
+    "tesseract"
+} else {
+    "none"
+};
+
+/// 检查OCR后端能否使用，可用时返回可识别的语言
+/// 与识别时使用相同的方式查找后端：macOS 上检查并运行 ocr 程序，Linux 上运行 tesseract，Windows 上还要确认能够创建OCR引擎
+pub async fn check_ocr_backend() -> std::result::Result<Vec<String>, (OcrError, String)> {
+    // 文件存在但没有执行权限时，运行时的错误信息不容易看懂，这里先检查
+    #[cfg(target_os = "macos")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let path = ocr_executable_path();
+        match std::fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() && metadata.permissions().mode() & 0o111 != 0 => {}
+            Ok(_) => {
+                return Err((OcrError::BinaryNotFound, format!("OCR executable at {:?} is not an executable file", path)));
+            }
+            Err(e) => {
+                return Err((OcrError::BinaryNotFound, format!("OCR executable not found at: {:?} ({})", path, e)));
+            }
+        }
+    }
+    
+    let languages = get_supported_recognition_languages().await;
+    if !languages.success {
+        return Err((
+            languages.error_code.unwrap_or(OcrError::EngineFailure),
+            languages.error_message.unwrap_or_default(),
+        ));
+    }
+    
+    #[cfg(target_os = "windows")]
+    create_ocr_engine(&None)?;
+    
+    Ok(languages.languages)
+}
+
+/// 检查OCR后端能否使用，供应用启动时提前提示安装问题，而不是等到识别时才报错
+/// 可用时返回 text 为空的成功结果，否则返回失败原因；检查方式见 check_ocr_backend
+#[command]
+pub async fn check_ocr_ready() -> OcrResult {
+    match check_ocr_backend().await {
+        Ok(_) => OcrResult::from_lines(vec![]),
+        Err((code, e)) => OcrResult::failure(code, e),
+    }
+}
+
+/// 快速识别一遍图片，根据识别出的文字猜测语言，按可能性从高到低返回语言标识
+/// 返回的语言可以直接用作 extract_text_with_system_ocr 的 languages；没有识别出文字时返回空列表
+/// macOS 上由 Vision 自动检测语言，再用 NLLanguageRecognizer 判断；
+/// Windows 上用用户配置文件语言的引擎识别，再按文字的书写系统在已安装的识别语言中挑选，
+/// 无法区分使用同一书写系统的语言（例如英语和法语）
+#[command]
+pub async fn detect_language(request: OcrRequest) -> SupportedLanguagesResult {
+    let _job = request.job_id.as_deref().map(OcrJob::register);
+    
+    let (image, _) = match prepare_image(&request) {
+        Ok(prepared) => prepared,
+        Err((code, e)) => return SupportedLanguagesResult::failure(code, e),
+    };
+    // 语言检测同样会运行OCR后端，与识别共用数量上限
+    let _permit = ocr_limit::acquire().await;
+    
+    #[cfg(target_os = "macos")]
+    {
+        detect_language_macos(image, &request).await
+    }
+    
+    #[cfg(target_os = "windows")]
+    {
+        detect_language_windows(image, &request).await
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = image;
+        SupportedLanguagesResult::failure(
+            OcrError::UnsupportedPlatform,
+            "Language detection is only available on macOS and Windows".to_string(),
+        )
+    }
+}
+
+#[cfg(target_os = "windows")]
+async fn extract_text_windows(image: ImageSource, request: &OcrRequest) -> OcrResult {
+    match create_ocr_engine(&request.languages) {
+        Ok(engine) => recognize_blocking_windows(image, engine, request.timeout_ms, cancel_flag(request)).await,
+        Err((code, e)) => OcrResult::failure(code, e),
+    }
+}
+
+/// 批量识别时按语言缓存OCR引擎，语言相同的图片复用同一个引擎
+#[cfg(target_os = "windows")]
+async fn extract_text_batch_windows(
+    images: Vec<(usize, ImageSource)>,
+    requests: &[OcrRequest],
+    progress: &BatchProgress,
+) -> Vec<(usize, OcrResult)> {
+    let mut engines: HashMap<Option<Vec<String>>, EngineResult> = HashMap::new();
+    let mut results = Vec::with_capacity(images.len());
+    
+    for (index, image) in images {
+        let languages = &requests[index].languages;
+        let engine = engines
+            .entry(languages.clone())
+            .or_insert_with(|| create_ocr_engine(languages));
+        let result = match engine {
+            Ok(engine) => {
+                let cancelled = cancel_flag(&requests[index]);
+                recognize_blocking_windows(image, engine.clone(), requests[index].timeout_ms, cancelled).await
+            }
+            Err((code, e)) => OcrResult::failure(*code, e.clone()),
+        };
+        progress.page_done(index, result.success);
+        results.push((index, result));
+    }
+    
+    results
+}
+
+/// 在阻塞线程池中执行 recognize_with_timeout_windows
+#[cfg(target_os = "windows")]
+async fn recognize_blocking_windows(
+    image: ImageSource,
+    engine: OcrEngine,
+    timeout_ms: Option<u64>,
+    cancelled: Option<Arc<AtomicBool>>,
+) -> OcrResult {
+    run_blocking(move || recognize_with_timeout_windows(image, engine, timeout_ms, cancelled))
+        .await
+        .unwrap_or_else(|e| OcrResult::failure(OcrError::EngineFailure, e))
+}
+
+/// 在单独的线程中识别，超过 timeout_ms 或任务被取消时不再等待并返回失败
+/// WinRT 的识别操作无法中途终止，超时或取消后线程会在后台继续运行到结束
+#[cfg(target_os = "windows")]
+fn recognize_with_timeout_windows(
+    image: ImageSource,
+    engine: OcrEngine,
+    timeout_ms: Option<u64>,
+    cancelled: Option<Arc<AtomicBool>>,
+) -> OcrResult {
+    use std::sync::mpsc::RecvTimeoutError;
+    use std::time::{Duration, Instant};
+    
+    if is_cancelled(cancelled.as_deref()) {
+        return OcrResult::failure(OcrError::Cancelled, OCR_CANCELLED.to_string());
+    }
+    if timeout_ms.is_none() && cancelled.is_none() {
+        return recognize_with_engine_windows(image, &engine);
+    }
+    
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(recognize_with_engine_windows(image, &engine));
+    });
+    
+    // 分小段等待，以便及时发现任务被取消
+    let deadline = timeout_ms.map(|timeout_ms| Instant::now() + Duration::from_millis(timeout_ms));
+    loop {
+        let mut wait = Duration::from_millis(50);
+        if let Some(deadline) = deadline {
+            wait = wait.min(deadline.saturating_duration_since(Instant::now()));
+        }
+        match receiver.recv_timeout(wait) {
+            Ok(result) => return result,
+            Err(RecvTimeoutError::Disconnected) => {
+                return OcrResult::failure(OcrError::EngineFailure, "OCR thread exited without a result".to_string());
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        if is_cancelled(cancelled.as_deref()) {
+            return OcrResult::failure(OcrError::Cancelled, OCR_CANCELLED.to_string());
+        }
+        if let (Some(deadline), Some(timeout_ms)) = (deadline, timeout_ms) {
+            if Instant::now() >= deadline {
+                return OcrResult::failure(OcrError::Timeout, format!("OCR timed out after {} ms", timeout_ms));
+            }
+        }
+    }
+}
+
+/// 使用已创建的OCR引擎识别一张图片
+#[cfg(target_os = "windows")]
+fn recognize_with_engine_windows(image: ImageSource, engine: &OcrEngine) -> OcrResult {
+    use futures::executor::block_on;
+    
+    // 执行OCR识别
+    let result = block_on(async {
+        // 图像数据直接写入内存流，图片文件通过 StorageFile 打开，都不需要临时文件
+        let stream = match &image {
+            ImageSource::Data(image_data) => open_memory_stream(image_data)?,
+            ImageSource::Path(image_path) => open_file_stream(image_path)?,
+        };
+
+        let decoder = BitmapDecoder::CreateAsync(&stream)
+            .map_err(|e| format!("Failed to create bitmap decoder: {:?}", e))?
+            .join()
+            .map_err(|e| format!("Failed to join bitmap decoder operation: {:?}", e))?;
+            
+        let bitmap = decoder.GetSoftwareBitmapAsync()
+            .map_err(|e| format!("Failed to get software bitmap: {:?}", e))?
+            .join()
+            .map_err(|e| format!("Failed to join software bitmap operation: {:?}", e))?;
+            
+        let ocr_result = engine.RecognizeAsync(&bitmap)
+            .map_err(|e| format!("Failed to recognize text: {:?}", e))?
+            .join()
+            .map_err(|e| format!("Failed to join OCR operation: {:?}", e))?;
+
+        // 用于将像素坐标归一化到 0..1
+        let image_width = bitmap.PixelWidth()
+            .map_err(|e| format!("Failed to get bitmap width: {:?}", e))?
+            .max(1) as f64;
+        let image_height = bitmap.PixelHeight()
+            .map_err(|e| format!("Failed to get bitmap height: {:?}", e))?
+            .max(1) as f64;
+
+        // 使用 Lines() 方法获取每行文字
+        let lines = ocr_result.Lines()
+            .map_err(|e| format!("Failed to get OCR result lines: {:?}", e))?;
+        
+        let mut ocr_lines = Vec::new();
+        for line in lines {
+            let text = line.Text()
+                .map(|hstring| hstring.to_string())
+                .unwrap_or_default();
+            
+            // OcrLine 没有位置信息，取该行所有单词外接矩形的并集
+            let (mut left, mut top) = (f64::MAX, f64::MAX);
+            let (mut right, mut bottom) = (f64::MIN, f64::MIN);
+            let words = line.Words()
+                .map_err(|e| format!("Failed to get OCR line words: {:?}", e))?;
+            for word in words {
+                if let Ok(rect) = word.BoundingRect() {
+                    left = left.min(rect.X as f64);
+                    top = top.min(rect.Y as f64);
+                    right = right.max((rect.X + rect.Width) as f64);
+                    bottom = bottom.max((rect.Y + rect.Height) as f64);
+                }
+            }
+            if left > right || top > bottom {
+                (left, top, right, bottom) = (0.0, 0.0, 0.0, 0.0);
+            }
+            
+            ocr_lines.push(OcrLine {
+                text,
+                x: left / image_width,
+                y: top / image_height,
+                width: (right - left) / image_width,
+                height: (bottom - top) / image_height,
+                // Windows OCR 不提供置信度
+                confidence: None,
+                reading_order_confidence: 0.0,
+            });
+        }
+        
+        Ok(ocr_lines)
+    });
+    
+    match result {
+        Ok(lines) => OcrResult::from_lines(lines),
+        Err(e) => OcrResult::failure(OcrError::EngineFailure, e),
+    }
+}
+
+/// 将内存中的图像数据写入 WinRT 内存流，供 BitmapDecoder 解码
+#[cfg(target_os = "windows")]
+fn open_memory_stream(image_data: &[u8]) -> std::result::Result<IRandomAccessStream, String> {
+    let stream = InMemoryRandomAccessStream::new()
+        .map_err(|e| format!("Failed to create in-memory stream: {:?}", e))?;
+    let writer = DataWriter::CreateDataWriter(&stream)
+        .map_err(|e| format!("Failed to create data writer: {:?}", e))?;
+    writer
+        .WriteBytes(image_data)
+        .map_err(|e| format!("Failed to write image data to stream: {:?}", e))?;
+    writer
+        .StoreAsync()
+        .map_err(|e| format!("Failed to store image data: {:?}", e))?
+        .join()
+        .map_err(|e| format!("Failed to join store operation: {:?}", e))?;
+    // 分离后关闭 writer 不会关闭流
+    writer
+        .DetachStream()
+        .map_err(|e| format!("Failed to detach data writer: {:?}", e))?;
+    stream
+        .Seek(0)
+        .map_err(|e| format!("Failed to rewind in-memory stream: {:?}", e))?;
+    stream
+        .cast()
+        .map_err(|e| format!("Failed to get random access stream: {:?}", e))
+}
+
+/// 以只读方式打开磁盘上的图片文件
+#[cfg(target_os = "windows")]
+fn open_file_stream(image_path: &std::path::Path) -> std::result::Result<IRandomAccessStream, String> {
+    // StorageFile 只接受绝对路径
+    let absolute_path = std::env::current_dir()
+        .map(|dir| dir.join(image_path))
+        .unwrap_or_else(|_| image_path.to_path_buf());
+    let file_path = absolute_path.to_str().unwrap_or("");
+    if file_path.is_empty() {
+        return Err("Failed to get image file path".to_string());
+    }
+    
+    let file = StorageFile::GetFileFromPathAsync(&HSTRING::from(file_path))
+        .map_err(|e| format!("Failed to get storage file: {:?}", e))?
+        .join()
+        .map_err(|e| format!("Failed to join storage file operation: {:?}", e))?;
+        
+    file.OpenAsync(FileAccessMode::Read)
+        .map_err(|e| format!("Failed to open file stream: {:?}", e))?
+        .join()
+        .map_err(|e| format!("Failed to join file stream operation: {:?}", e))
+}
+
+/// 创建OCR引擎的结果，失败时为错误分类和错误信息
+#[cfg(target_os = "windows")]
+type EngineResult = std::result::Result<OcrEngine, (OcrError, String)>;
+
+/// 根据请求的语言创建Windows OCR引擎
+/// 使用 languages 中第一个已安装语言包的语言；未指定语言时沿用用户配置文件中的语言
+/// 请求的语言包都未安装时返回错误，而不是悄悄退回到用户配置文件的语言；无效的语言标签会被跳过
+#[cfg(target_os = "windows")]
+fn create_ocr_engine(languages: &Option<Vec<String>>) -> EngineResult {
+    let tags = match languages.as_ref().filter(|languages| !languages.is_empty()) {
+        Some(tags) => tags,
+        None => {
+            return OcrEngine::TryCreateFromUserProfileLanguages()
+                .map_err(|e| engine_creation_error(format!("Failed to create OCR engine: {:?}", e)));
+        }
+    };
+    
+    // 无效的语言标签不影响后面的标签，只有所有标签都不可用时才报告
+    let mut invalid_tags = Vec::new();
+    let mut missing_tags = Vec::new();
+    for tag in tags {
+        let language = match Language::CreateLanguage(&HSTRING::from(tag.as_str())) {
+            Ok(language) => language,
+            Err(_) => {
+                invalid_tags.push(tag.as_str());
+                continue;
+            }
+        };
+        
+        let supported = OcrEngine::IsLanguageSupported(&language).map_err(|e| {
+            (OcrError::EngineFailure, format!("Failed to check OCR language support for {}: {:?}", tag, e))
+        })?;
+        if supported {
+            return OcrEngine::TryCreateFromLanguage(&language)
+                .map_err(|e| engine_creation_error(format!("Failed to create OCR engine for {}: {:?}", tag, e)));
+        }
+        missing_tags.push(tag.as_str());
+    }
+    
+    let mut problems = Vec::new();
+    if !missing_tags.is_empty() {
+        problems.push(format!(
+            "OCR language pack for {} is not installed. Add it in Settings > Time & Language > Language & region",
+            missing_tags.join(", ")
+        ));
+    }
+    if !invalid_tags.is_empty() {
+        problems.push(format!("Invalid OCR language tag: {}", invalid_tags.join(", ")));
+    }
+    Err((OcrError::UnsupportedLanguage, problems.join("; ")))
+}
+
+/// 创建OCR引擎失败时查询已安装的OCR语言，给出用户可以据此处理的错误
+/// 没有安装任何OCR语言包时（最常见的原因）提示去设置中安装，否则在错误中附上可用的语言
+#[cfg(target_os = "windows")]
+fn engine_creation_error(cause: String) -> (OcrError, String) {
+    match available_ocr_languages() {
+        Ok(languages) if languages.is_empty() => (
+            OcrError::UnsupportedLanguage,
+            "No OCR language pack is installed. Add a language with the Optical character recognition feature in Settings > Time & Language > Language & region".to_string(),
+        ),
+        Ok(languages) => (
+            OcrError::EngineFailure,
+            format!("{} (installed OCR languages: {})", cause, languages.join(", ")),
+        ),
+        Err(_) => (OcrError::EngineFailure, cause),
+    }
+}
+
+/// 已安装语言包、可用于OCR的语言标签
+#[cfg(target_os = "windows")]
+fn available_ocr_languages() -> std::result::Result<Vec<String>, String> {
+    OcrEngine::AvailableRecognizerLanguages()
+        .map_err(|e| format!("Failed to get available OCR languages: {:?}", e))
+        .and_then(|languages| {
+            languages
+                .into_iter()
+                .map(|language| {
+                    language
+                        .LanguageTag()
+                        .map(|tag| tag.to_string())
+                        .map_err(|e| format!("Failed to get OCR language tag: {:?}", e))
+                })
+                .collect::<std::result::Result<Vec<String>, String>>()
+        })
+}
+
+/// 获取已安装语言包、可用于OCR的语言
+#[cfg(target_os = "windows")]
+async fn get_supported_languages_windows() -> SupportedLanguagesResult {
+    match available_ocr_languages() {
+        Ok(languages) => SupportedLanguagesResult::from_languages(languages),
+        Err(e) => SupportedLanguagesResult::failure(OcrError::EngineFailure, e),
+    }
+}
+
+#[cfg(target_os = "windows")]
+async fn detect_language_windows(image: ImageSource, request: &OcrRequest) -> SupportedLanguagesResult {
+    let available = get_supported_languages_windows().await;
+    if !available.success {
+        return available;
+    }
+    
+    let engine = match create_ocr_engine(&None) {
+        Ok(engine) => engine,
+        Err((code, e)) => return SupportedLanguagesResult::failure(code, e),
+    };
+    let result = recognize_blocking_windows(image, engine, request.timeout_ms, cancel_flag(request)).await;
+    if !result.success {
+        return SupportedLanguagesResult::failure(
+            result.error_code.unwrap_or(OcrError::EngineFailure),
+            result.error_message.unwrap_or_default(),
+        );
+    }
+    
+    SupportedLanguagesResult::from_languages(rank_languages_by_script(&result.text, &available.languages))
+}
+
+/// 书写系统，用于根据识别出的文字猜测语言
+#[cfg(target_os = "windows")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Script {
+    Latin,
+    Han,
+    Kana,
+    Hangul,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Thai,
+    Devanagari,
+}
+
+#[cfg(target_os = "windows")]
+fn script_of(c: char) -> Option<Script> {
+    match c as u32 {
+        0x3040..=0x30FF => Some(Script::Kana),
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Some(Script::Hangul),
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0x20000..=0x2EBEF => Some(Script::Han),
+        0x0370..=0x03FF => Some(Script::Greek),
+        0x0400..=0x04FF => Some(Script::Cyrillic),
+        0x0590..=0x05FF => Some(Script::Hebrew),
+        0x0600..=0x06FF => Some(Script::Arabic),
+        0x0900..=0x097F => Some(Script::Devanagari),
+        0x0E00..=0x0E7F => Some(Script::Thai),
+        code if code <= 0x024F && c.is_alphabetic() => Some(Script::Latin),
+        _ => None,
+    }
+}
+
+// 各语言（主语言子标签）使用的书写系统，未列出的语言视为使用拉丁字母
+#[cfg(target_os = "windows")]
+const LANGUAGE_SCRIPTS: &[(&str, &[Script])] = &[
+    ("ja", &[Script::Kana, Script::Han]),
+    ("zh", &[Script::Han]),
+    ("ko", &[Script::Hangul]),
+    ("ru", &[Script::Cyrillic]),
+    ("uk", &[Script::Cyrillic]),
+    ("bg", &[Script::Cyrillic]),
+    ("sr", &[Script::Cyrillic]),
+    ("el", &[Script::Greek]),
+    ("ar", &[Script::Arabic]),
+    ("fa", &[Script::Arabic]),
+    ("ur", &[Script::Arabic]),
+    ("he", &[Script::Hebrew]),
+    ("th", &[Script::Thai]),
+    ("hi", &[Script::Devanagari]),
+    ("mr", &[Script::Devanagari]),
+    ("ne", &[Script::Devanagari]),
+];
+
+/// 按识别出的文字中各书写系统的字数给语言排序，去掉没有对应文字的语言；得分相同时保持原顺序
+/// 日语和中文都使用汉字，没有假名时日语只计一半的汉字，使中文排在前面
+#[cfg(target_os = "windows")]
+fn rank_languages_by_script(text: &str, languages: &[String]) -> Vec<String> {
+    let mut counts: HashMap<Script, usize> = HashMap::new();
+    for script in text.chars().filter_map(script_of) {
+        *counts.entry(script).or_insert(0) += 1;
+    }
+    let count = |script: Script| counts.get(&script).copied().unwrap_or(0);
+    
+    let mut scored: Vec<(usize, &String)> = languages
+        .iter()
+        .map(|language| {
+            let primary = language.split('-').next().unwrap_or(language).to_lowercase();
+            let scripts = LANGUAGE_SCRIPTS
+                .iter()
+                .find(|(code, _)| *code == primary)
+                .map_or(&[Script::Latin][..], |(_, scripts)| *scripts);
+            let score = if primary == "ja" && count(Script::Kana) == 0 {
+                count(Script::Han) / 2
+            } else {
+                scripts.iter().map(|&script| count(script)).sum()
+            };
+            (score, language)
+        })
+        .filter(|(score, _)| *score > 0)
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    
+    scored.into_iter().map(|(_, language)| language.clone()).collect()
+}
+
+/// 在阻塞线程池中执行会阻塞的识别步骤（等待OCR进程、WinRT 操作、渲染PDF页面），不占用异步运行时的工作线程，
+/// 识别期间 cancel_ocr 等命令仍能及时执行；执行中 panic 时返回错误
+async fn run_blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> std::result::Result<T, String> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("OCR worker thread failed: {}", e))
+}
+
+/// 在阻塞线程池中执行 output_with_timeout
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+async fn run_command(
+    mut cmd: Command,
+    timeout_ms: Option<u64>,
+    cancelled: Option<Arc<AtomicBool>>,
+) -> std::io::Result<std::process::Output> {
+    run_blocking(move || output_with_timeout(&mut cmd, timeout_ms, cancelled.as_deref(), None))
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+}
+
+/// 执行命令并收集输出；指定了 timeout_ms 时超时会终止进程，返回 TimedOut 错误
+/// 任务被取消时同样终止进程，返回 Interrupted 错误
+/// 指定了 on_stdout_line 时，进程运行期间每输出一行就调用一次（不含换行符），用于报告进度
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn output_with_timeout(
+    cmd: &mut Command,
+    timeout_ms: Option<u64>,
+    cancelled: Option<&AtomicBool>,
+    mut on_stdout_line: Option<&mut dyn FnMut(&str)>,
+) -> std::io::Result<std::process::Output> {
+    use std::io::{BufRead, Read};
+    use std::process::Stdio;
+    use std::time::{Duration, Instant};
+    
+    if is_cancelled(cancelled) {
+        return Err(std::io::Error::new(std::io::ErrorKind::Interrupted, OCR_CANCELLED));
+    }
+    if timeout_ms.is_none() && cancelled.is_none() && on_stdout_line.is_none() {
+        return cmd.output();
+    }
+    
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    
+    // 在单独的线程中读取输出，避免输出填满管道后子进程阻塞；读到的每一行同时转发给等待进程的线程
+    let stdout = child.stdout.take();
+    let mut stderr = child.stderr.take();
+    let (line_sender, line_receiver) = std::sync::mpsc::channel::<String>();
+    let stdout_reader = std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(stdout) = stdout {
+            let mut stdout = std::io::BufReader::new(stdout);
+            let mut line = Vec::new();
+            while matches!(stdout.read_until(b'\n', &mut line), Ok(read) if read > 0) {
+                let _ = line_sender.send(String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n']).to_string());
+                buffer.append(&mut line);
+            }
+        }
+        buffer
+    });
+    let forward_lines = |on_stdout_line: &mut Option<&mut dyn FnMut(&str)>| {
+        for line in line_receiver.try_iter() {
+            if let Some(on_stdout_line) = on_stdout_line.as_mut() {
+                on_stdout_line(&line);
+            }
+        }
+    };
+    let stderr_reader = std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(stderr) = stderr.as_mut() {
+            let _ = stderr.read_to_end(&mut buffer);
+        }
+        buffer
+    });
+    
+    let deadline = timeout_ms.map(|timeout_ms| Instant::now() + Duration::from_millis(timeout_ms));
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        let error = if is_cancelled(cancelled) {
+            Some(std::io::Error::new(std::io::ErrorKind::Interrupted, OCR_CANCELLED))
+        } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            Some(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("OCR timed out after {} ms", timeout_ms.unwrap_or_default()),
+            ))
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(error);
+        }
+        forward_lines(&mut on_stdout_line);
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    
+    let stdout = stdout_reader.join().unwrap_or_default();
+    forward_lines(&mut on_stdout_line);
+    Ok(std::process::Output {
+        status,
+        stdout,
+        stderr: stderr_reader.join().unwrap_or_default(),
+    })
+}
+
+/// 获取OCR可执行文件路径
+/// 首先尝试从环境变量获取（由build.rs设置），否则在当前可执行文件目录查找
+#[cfg(target_os = "macos")]
+fn ocr_executable_path() -> std::path::PathBuf {
+    if let Ok(path) = std::env::var("OCR_EXECUTABLE_PATH") {
+        std::path::PathBuf::from(path)
+    } else {
+        let exe_path = std::env::current_exe().unwrap_or_else(|_| std::path::PathBuf::from("./"));
+        let exe_dir = exe_path.parent().unwrap_or_else(|| std::path::Path::new("."));
+        exe_dir.join("ocr")
+    }
+}
+
+#[cfg(target_os = "macos")]
+async fn get_supported_languages_macos() -> SupportedLanguagesResult {
+    let ocr_executable_path = ocr_executable_path();
+    
+    // 检查OCR可执行文件是否存在
+    if !ocr_executable_path.exists() {
+        return SupportedLanguagesResult::failure(
+            OcrError::BinaryNotFound,
+            format!("OCR executable not found at: {:?}", ocr_executable_path),
+        );
+    }
+    
+    // 执行OCR程序获取支持的语言
+    let output = Command::new(&ocr_executable_path)
+        .output();
+    
+    match output {
+        Ok(output) => {
+            if output.status.success() {
+                let output_str = String::from_utf8_lossy(&output.stdout);
+                parse_supported_languages_output(&output_str)
+            } else {
+                let error = String::from_utf8_lossy(&output.stderr);
+                SupportedLanguagesResult::failure(
+                    OcrError::EngineFailure,
+                    format!("Failed to get supported languages: {}", error),
+                )
+            }
+        }
+        Err(e) => {
+            SupportedLanguagesResult::failure(
+                OcrError::EngineFailure,
+                format!("Failed to execute OCR to get supported languages: {}", e),
+            )
         }
     }
+}
+
+/// 解析OCR程序输出的语言列表
+/// 语言列表为空时（例如系统版本过旧，Vision不支持文字识别）返回明确的错误，而不是空的成功结果
+#[cfg(target_os = "macos")]
+fn parse_supported_languages_output(output: &str) -> SupportedLanguagesResult {
+    let lines: Vec<&str> = output.lines().collect();
     
-    // 执行OCR程序
-    let output = cmd.output();
+    // 查找语言列表的开始和结束标记
+    let start_index = lines.iter().position(|&line| line == "SUPPORTED_LANGUAGES_START");
+    let end_index = lines.iter().position(|&line| line == "SUPPORTED_LANGUAGES_END");
+    
+    let (start, end) = match (start_index, end_index) {
+        (Some(start), Some(end)) if start < end => (start, end),
+        _ => {
+            return SupportedLanguagesResult::failure(
+                OcrError::EngineFailure,
+                "Failed to parse supported languages from OCR output".to_string(),
+            );
+        }
+    };
+    
+    // 提取语言列表
+    let languages: Vec<String> = lines[start+1..end]
+        .iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    
+    if languages.is_empty() {
+        return SupportedLanguagesResult::failure(
+            OcrError::UnsupportedPlatform,
+            "No OCR languages available; macOS 10.15+ required for text recognition".to_string(),
+        );
+    }
+    
+    SupportedLanguagesResult::from_languages(languages)
+}
+
+/// 解析OCR程序的输出：每一行是一个 JSON 对象，对应一行识别结果
+#[cfg(target_os = "macos")]
+fn parse_ocr_output(output: &str) -> std::result::Result<Vec<OcrLine>, String> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str::<OcrLine>(line)
+                .map_err(|e| format!("Failed to parse OCR output line {:?}: {}", line, e))
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+async fn extract_text_macos(image: ImageSource, request: &OcrRequest) -> OcrResult {
+    let ocr_executable_path = ocr_executable_path();
+    
+    // 检查OCR可执行文件是否存在
+    if !ocr_executable_path.exists() {
+        return OcrResult::failure(
+            OcrError::BinaryNotFound,
+            format!("OCR executable not found at: {:?}", ocr_executable_path),
+        );
+    }
+    
+    // 图片文件直接使用，图像数据写入临时文件，image_file 离开作用域时删除临时文件
+    let image_file = match image.into_file() {
+        Ok(image_file) => image_file,
+        Err(e) => return OcrResult::failure(OcrError::EngineFailure, e),
+    };
+    
+    // 构建命令参数：ocr [--level fast|accurate] [--lang 语言1 --lang 语言2 ...] <图片>
+    let mut cmd = Command::new(&ocr_executable_path);
+    if let Some(level) = request.recognition_level {
+        cmd.arg("--level").arg(level.as_arg());
+    }
+    // 每个语言单独作为一个参数，未提供语言时由OCR程序使用默认语言
+    for language in request.languages.iter().flatten() {
+        cmd.arg("--lang").arg(language);
+    }
+    cmd.arg(image_file.path());
     
-    // 清理临时文件
-    let _ = std::fs::remove_file(&temp_file_path);
+    // 执行OCR程序
+    let output = run_command(cmd, request.timeout_ms, cancel_flag(request)).await;
     
     match output {
         Ok(output) => {
             if output.status.success() {
-                let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                OcrResult {
-                    text,
-                    success: true,
-                    error_message: None,
+                let output_str = String::from_utf8_lossy(&output.stdout);
+                match parse_ocr_output(&output_str) {
+                    Ok(lines) => OcrResult::from_lines(lines),
+                    Err(e) => OcrResult::failure(OcrError::EngineFailure, e),
                 }
             } else {
                 let error = String::from_utf8_lossy(&output.stderr);
-                OcrResult {
-                    text: String::new(),
-                    success: false,
-                    error_message: Some(format!("OCR failed: {}", error)),
+                OcrResult::failure(OcrError::EngineFailure, format!("OCR failed: {}", error))
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            OcrResult::failure(OcrError::Timeout, e.to_string())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+            OcrResult::failure(OcrError::Cancelled, e.to_string())
+        }
+        Err(e) => {
+            OcrResult::failure(OcrError::EngineFailure, format!("Failed to execute OCR: {}", e))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+async fn detect_language_macos(image: ImageSource, request: &OcrRequest) -> SupportedLanguagesResult {
+    let ocr_executable_path = ocr_executable_path();
+    
+    // 检查OCR可执行文件是否存在
+    if !ocr_executable_path.exists() {
+        return SupportedLanguagesResult::failure(
+            OcrError::BinaryNotFound,
+            format!("OCR executable not found at: {:?}", ocr_executable_path),
+        );
+    }
+    
+    let image_file = match image.into_file() {
+        Ok(image_file) => image_file,
+        Err(e) => return SupportedLanguagesResult::failure(OcrError::EngineFailure, e),
+    };
+    
+    // ocr --detect-language <图片>，每行输出一个语言及其概率，已按概率从高到低排列
+    let mut cmd = Command::new(&ocr_executable_path);
+    cmd.arg("--detect-language").arg(image_file.path());
+    
+    match run_command(cmd, request.timeout_ms, cancel_flag(request)).await {
+        Ok(output) if output.status.success() => {
+            let languages = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| line.split_whitespace().next())
+                .map(|tag| tag.to_string())
+                .collect();
+            SupportedLanguagesResult::from_languages(languages)
+        }
+        Ok(output) => {
+            let error = String::from_utf8_lossy(&output.stderr);
+            SupportedLanguagesResult::failure(OcrError::EngineFailure, format!("Language detection failed: {}", error))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            SupportedLanguagesResult::failure(OcrError::Timeout, e.to_string())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+            SupportedLanguagesResult::failure(OcrError::Cancelled, e.to_string())
+        }
+        Err(e) => {
+            SupportedLanguagesResult::failure(OcrError::EngineFailure, format!("Failed to execute OCR: {}", e))
+        }
+    }
+}
+
+/// 解析批量模式的输出
+/// 每张图片的结果以 IMAGE_START / IMAGE_END 包围，识别失败的图片输出一行 IMAGE_ERROR <错误信息>
+/// 输出不完整时（例如OCR程序中途崩溃），缺少结果的图片记为失败
+#[cfg(target_os = "macos")]
+fn parse_batch_output(output: &str, count: usize) -> Vec<std::result::Result<Vec<OcrLine>, String>> {
+    let mut results = Vec::with_capacity(count);
+    let mut current: Option<Vec<&str>> = None;
+    
+    for line in output.lines() {
+        if line == "IMAGE_START" {
+            current = Some(Vec::new());
+        } else if line == "IMAGE_END" {
+            if let Some(lines) = current.take() {
+                results.push(parse_ocr_output(&lines.join("\n")));
+            }
+        } else if let Some(message) = line.strip_prefix("IMAGE_ERROR ") {
+            current = None;
+            results.push(Err(format!("OCR failed: {}", message)));
+        } else if let Some(lines) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    
+    results.truncate(count);
+    while results.len() < count {
+        results.push(Err("OCR produced no result for this image".to_string()));
+    }
+    results
+}
+
+/// 批量识别的分组依据：识别语言、识别精度和任务ID
+#[cfg(target_os = "macos")]
+type BatchGroupKey = (Option<Vec<String>>, Option<RecognitionLevel>, Option<String>);
+
+/// 批量识别：语言和精度相同的图片只启动一次OCR程序，Vision 引擎只需加载一次
+/// 不同任务的图片分开识别，取消一个任务只终止该任务的OCR程序，之后的分组开始前也会检查是否已取消
+#[cfg(target_os = "macos")]
+async fn extract_text_batch_macos(
+    images: Vec<(usize, ImageSource)>,
+    requests: &[OcrRequest],
+    progress: &Arc<BatchProgress>,
+) -> Vec<(usize, OcrResult)> {
+    let ocr_executable_path = ocr_executable_path();
+    if !ocr_executable_path.exists() {
+        let message = format!("OCR executable not found at: {:?}", ocr_executable_path);
+        return images
+            .into_iter()
+            .map(|(index, _)| {
+                progress.page_done(index, false);
+                (index, OcrResult::failure(OcrError::BinaryNotFound, message.clone()))
+            })
+            .collect();
+    }
+    
+    // 按识别语言、识别精度和任务分组，每组启动一次OCR程序
+    let mut groups: Vec<(BatchGroupKey, Vec<_>)> = Vec::new();
+    for (index, image) in images {
+        let languages = requests[index].languages.clone().filter(|languages| !languages.is_empty());
+        let key = (languages, requests[index].recognition_level, requests[index].job_id.clone());
+        match groups.iter_mut().find(|(group_key, _)| *group_key == key) {
+            Some((_, group)) => group.push((index, image)),
+            None => groups.push((key, vec![(index, image)])),
+        }
+    }
+    
+    let mut results = Vec::new();
+    for ((languages, level, _), group) in groups {
+        // 同一组的图片属于同一个任务，共用一个取消标志
+        let cancelled = group.first().and_then(|(index, _)| cancel_flag(&requests[*index]));
+        if is_cancelled(cancelled.as_deref()) {
+            for (index, _) in group {
+                progress.page_done(index, false);
+                results.push((index, OcrResult::failure(OcrError::Cancelled, OCR_CANCELLED.to_string())));
+            }
+            continue;
+        }
+        
+        // 图片文件直接使用，图像数据写入临时文件，写入失败的图片直接记为失败
+        // 临时文件在本组处理完、image_files 被消耗后删除
+        let mut image_files: Vec<(usize, ImageFile)> = Vec::new();
+        for (index, image) in group {
+            match image.into_file() {
+                Ok(image_file) => image_files.push((index, image_file)),
+                Err(e) => {
+                    progress.page_done(index, false);
+                    results.push((index, OcrResult::failure(OcrError::EngineFailure, e)));
+                }
+            }
+        }
+        
+        if image_files.is_empty() {
+            continue;
+        }
+        
+        // ocr --batch [--lang 语言1 --lang 语言2 ...] [--level fast|accurate] <图片1> <图片2> ...
+        let mut cmd = Command::new(&ocr_executable_path);
+        cmd.arg("--batch");
+        for language in languages.iter().flatten() {
+            cmd.arg("--lang").arg(language);
+        }
+        if let Some(level) = level {
+            cmd.arg("--level").arg(level.as_arg());
+        }
+        cmd.args(image_files.iter().map(|(_, image_file)| image_file.path()));
+        
+        // 整组的超时时间为各图片超时时间之和，有任意一张不限时则整组不限时
+        let timeout_ms = image_files
+            .iter()
+            .map(|(index, _)| requests[*index].timeout_ms)
+            .sum::<Option<u64>>();
+        
+        // OCR程序每输出一个 IMAGE_END 或 IMAGE_ERROR 就完成了一张图片，边识别边报告进度
+        // 在阻塞线程池中等待OCR程序，返回输出和已报告进度的图片数
+        let indexes: Vec<usize> = image_files.iter().map(|(index, _)| *index).collect();
+        let batch_progress = progress.clone();
+        let (output, reported) = run_blocking(move || {
+            let mut reported = 0;
+            let mut report_progress = |line: &str| {
+                let success = if line == "IMAGE_END" {
+                    true
+                } else if line.starts_with("IMAGE_ERROR ") {
+                    false
+                } else {
+                    return;
+                };
+                if let Some(index) = indexes.get(reported) {
+                    batch_progress.page_done(*index, success);
+                }
+                reported += 1;
+            };
+            let output = output_with_timeout(&mut cmd, timeout_ms, cancelled.as_deref(), Some(&mut report_progress));
+            (output, reported)
+        })
+        .await
+        .unwrap_or_else(|e| (Err(std::io::Error::other(e)), 0));
+        
+        let parsed = match output {
+            Ok(output) if output.status.success() => {
+                let output_str = String::from_utf8_lossy(&output.stdout);
+                parse_batch_output(&output_str, image_files.len())
+                    .into_iter()
+                    .map(|result| result.map_err(|e| (OcrError::EngineFailure, e)))
+                    .collect()
+            }
+            Ok(output) => {
+                let error = String::from_utf8_lossy(&output.stderr);
+                vec![Err((OcrError::EngineFailure, format!("OCR failed: {}", error))); image_files.len()]
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                vec![Err((OcrError::Timeout, e.to_string())); image_files.len()]
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                vec![Err((OcrError::Cancelled, e.to_string())); image_files.len()]
+            }
+            Err(e) => vec![Err((OcrError::EngineFailure, format!("Failed to execute OCR: {}", e))); image_files.len()],
+        };
+        
+        for (position, ((index, _), result)) in image_files.into_iter().zip(parsed).enumerate() {
+            let result = match result {
+                Ok(lines) => guard_page(|| OcrResult::from_lines(lines)),
+                Err((code, e)) => OcrResult::failure(code, e),
+            };
+            // 程序失败、超时或被取消时没有输出的图片在这里报告
+            if position >= reported {
+                progress.page_done(index, result.success);
+            }
+            results.push((index, result));
+        }
+    }
+    
+    results
+}
+
+/// BCP-47 语言代码与 Tesseract traineddata 名称的对应关系
+/// 同一个 Tesseract 语言出现多次时，第一条作为反向映射的结果
+#[cfg(target_os = "linux")]
+const TESSERACT_LANGUAGES: &[(&str, &str)] = &[
+    ("en-US", "eng"),
+    ("en-GB", "eng"),
+    ("zh-Hans", "chi_sim"),
+    ("zh-CN", "chi_sim"),
+    ("zh-SG", "chi_sim"),
+    ("zh-Hant", "chi_tra"),
+    ("zh-TW", "chi_tra"),
+    ("zh-HK", "chi_tra"),
+    ("ja-JP", "jpn"),
+    ("ko-KR", "kor"),
+    ("fr-FR", "fra"),
+    ("de-DE", "deu"),
+    ("es-ES", "spa"),
+    ("it-IT", "ita"),
+    ("pt-BR", "por"),
+    ("ru-RU", "rus"),
+    ("uk-UA", "ukr"),
+    ("ar-SA", "ara"),
+    ("vi-VN", "vie"),
+    ("th-TH", "tha"),
+    ("nl-NL", "nld"),
+    ("pl-PL", "pol"),
+    ("tr-TR", "tur"),
+];
+
+/// 将 BCP-47 语言代码转换为 Tesseract 的语言名称
+/// 先完整匹配（zh-CN、zh-Hant），再按主语言匹配（en、ja）；无法识别的代码原样传给 Tesseract
+#[cfg(target_os = "linux")]
+fn bcp47_to_tesseract(tag: &str) -> String {
+    if let Some((_, name)) = TESSERACT_LANGUAGES.iter().find(|(bcp47, _)| bcp47.eq_ignore_ascii_case(tag)) {
+        return name.to_string();
+    }
+    
+    let primary = tag.split(['-', '_']).next().unwrap_or(tag);
+    TESSERACT_LANGUAGES
+        .iter()
+        .find(|(bcp47, _)| bcp47.split('-').next().is_some_and(|p| p.eq_ignore_ascii_case(primary)))
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| tag.to_string())
+}
+
+/// 将 Tesseract 的语言名称转换回 BCP-47 语言代码，无法识别时保留原名称
+#[cfg(target_os = "linux")]
+fn tesseract_to_bcp47(name: &str) -> String {
+    TESSERACT_LANGUAGES
+        .iter()
+        .find(|(_, tesseract)| *tesseract == name)
+        .map(|(bcp47, _)| bcp47.to_string())
+        .unwrap_or_else(|| name.to_string())
+}
+
+#[cfg(target_os = "linux")]
+const TESSERACT_NOT_FOUND: &str = "tesseract was not found on PATH. Install it (e.g. `sudo apt install tesseract-ocr`) to use system OCR on Linux";
+
+#[cfg(target_os = "linux")]
+async fn get_supported_languages_linux() -> SupportedLanguagesResult {
+    let output = Command::new("tesseract")
+        .arg("--list-langs")
+        .output();
+    
+    match output {
+        Ok(output) => {
+            if output.status.success() {
+                // 较旧版本的 Tesseract 将语言列表输出到 stderr
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let output_str = if stdout.trim().is_empty() {
+                    String::from_utf8_lossy(&output.stderr)
+                } else {
+                    stdout
+                };
+                
+                // 第一行是 "List of available languages ..." 标题；osd 是方向检测数据，不是语言
+                let mut languages: Vec<String> = Vec::new();
+                for name in output_str.lines().skip(1).map(|line| line.trim()) {
+                    if name.is_empty() || name == "osd" {
+                        continue;
+                    }
+                    let language = tesseract_to_bcp47(name);
+                    if !languages.contains(&language) {
+                        languages.push(language);
+                    }
                 }
+                
+                SupportedLanguagesResult::from_languages(languages)
+            } else {
+                let error = String::from_utf8_lossy(&output.stderr);
+                SupportedLanguagesResult::failure(
+                    OcrError::EngineFailure,
+                    format!("Failed to get supported languages: {}", error),
+                )
             }
         }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            SupportedLanguagesResult::failure(OcrError::BinaryNotFound, TESSERACT_NOT_FOUND.to_string())
+        }
         Err(e) => {
-            OcrResult {
-                text: String::new(),
-                success: false,
-                error_message: Some(format!("Failed to execute OCR: {}", e)),
+            SupportedLanguagesResult::failure(OcrError::EngineFailure, format!("Failed to execute tesseract: {}", e))
+        }
+    }
+}
+
+/// 将 Tesseract 的 tsv 输出按行合并为 OcrLine
+/// tsv 每一行是一个版面元素：level 1 为整页（给出图片尺寸），level 5 为单词，
+/// 同一 block/par/line 编号下的单词属于同一行
+#[cfg(target_os = "linux")]
+fn parse_tesseract_tsv(tsv: &str) -> Vec<OcrLine> {
+    struct TsvLine<'a> {
+        key: (&'a str, &'a str, &'a str),
+        words: Vec<&'a str>,
+        left: f64,
+        top: f64,
+        right: f64,
+        bottom: f64,
+        confidence_sum: f64,
+        confidence_count: u32,
+    }
+    
+    let mut page_width = 1.0;
+    let mut page_height = 1.0;
+    let mut tsv_lines: Vec<TsvLine> = Vec::new();
+    
+    // 第一行是表头: level page_num block_num par_num line_num word_num left top width height conf text
+    for row in tsv.lines().skip(1) {
+        let columns: Vec<&str> = row.split('\t').collect();
+        if columns.len() < 12 {
+            continue;
+        }
+        
+        let number = |index: usize| columns[index].trim().parse::<f64>().unwrap_or(0.0);
+        let (left, top, width, height) = (number(6), number(7), number(8), number(9));
+        
+        match columns[0] {
+            "1" => {
+                page_width = width.max(1.0);
+                page_height = height.max(1.0);
+            }
+            "5" => {
+                let word = columns[11].trim();
+                if word.is_empty() {
+                    continue;
+                }
+                
+                let key = (columns[2], columns[3], columns[4]);
+                if tsv_lines.last().map_or(true, |line| line.key != key) {
+                    tsv_lines.push(TsvLine {
+                        key,
+                        words: Vec::new(),
+                        left: f64::MAX,
+                        top: f64::MAX,
+                        right: f64::MIN,
+                        bottom: f64::MIN,
+                        confidence_sum: 0.0,
+                        confidence_count: 0,
+                    });
+                }
+                
+                if let Some(line) = tsv_lines.last_mut() {
+                    line.words.push(word);
+                    line.left = line.left.min(left);
+                    line.top = line.top.min(top);
+                    line.right = line.right.max(left + width);
+                    line.bottom = line.bottom.max(top + height);
+                    // 置信度为 0~100，-1 表示没有置信度
+                    let confidence = number(10);
+                    if confidence >= 0.0 {
+                        line.confidence_sum += confidence;
+                        line.confidence_count += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    tsv_lines
+        .into_iter()
+        .map(|line| OcrLine {
+            text: line.words.join(" "),
+            x: line.left / page_width,
+            y: line.top / page_height,
+            width: (line.right - line.left) / page_width,
+            height: (line.bottom - line.top) / page_height,
+            confidence: if line.confidence_count > 0 {
+                Some((line.confidence_sum / line.confidence_count as f64 / 100.0) as f32)
+            } else {
+                None
+            },
+            reading_order_confidence: 0.0,
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+async fn extract_text_linux(image: ImageSource, request: &OcrRequest) -> OcrResult {
+    // 图片文件直接使用，图像数据写入临时文件，image_file 离开作用域时删除临时文件
+    let image_file = match image.into_file() {
+        Ok(image_file) => image_file,
+        Err(e) => return OcrResult::failure(OcrError::EngineFailure, e),
+    };
+    
+    // tesseract <图片> stdout [-l 语言1+语言2]
+    let mut cmd = Command::new("tesseract");
+    cmd.arg(image_file.path()).arg("stdout");
+    
+    if let Some(languages) = &request.languages {
+        if !languages.is_empty() {
+            let languages_str = languages
+                .iter()
+                .map(|language| bcp47_to_tesseract(language))
+                .collect::<Vec<String>>()
+                .join("+");
+            cmd.arg("-l").arg(languages_str);
+        }
+    }
+    
+    // 使用 tsv 输出以获得每个单词的位置和置信度
+    cmd.arg("tsv");
+    
+    // 执行OCR程序
+    let output = run_command(cmd, request.timeout_ms, cancel_flag(request)).await;
+    
+    match output {
+        Ok(output) => {
+            if output.status.success() {
+                let output_str = String::from_utf8_lossy(&output.stdout);
+                OcrResult::from_lines(parse_tesseract_tsv(&output_str))
+            } else {
+                let error = String::from_utf8_lossy(&output.stderr);
+                // 请求的语言没有安装对应的 traineddata 时 tesseract 输出 "Failed loading language"
+                let code = if error.contains("Failed loading language") {
+                    OcrError::UnsupportedLanguage
+                } else {
+                    OcrError::EngineFailure
+                };
+                OcrResult::failure(code, format!("OCR failed: {}", error))
             }
         }
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            OcrResult::failure(OcrError::Timeout, e.to_string())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+            OcrResult::failure(OcrError::Cancelled, e.to_string())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            OcrResult::failure(OcrError::BinaryNotFound, TESSERACT_NOT_FOUND.to_string())
+        }
+        Err(e) => {
+            OcrResult::failure(OcrError::EngineFailure, format!("Failed to execute tesseract: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造一行识别结果，各行从上到下依次排列
+    fn line(text: &str, index: usize) -> OcrLine {
+        OcrLine {
+            text: text.to_string(),
+            x: 0.1,
+            y: 0.1 + index as f64 * 0.05,
+            width: 0.8,
+            height: 0.04,
+            confidence: None,
+            reading_order_confidence: 0.0,
+        }
+    }
+
+    fn result_from(texts: &[&str]) -> OcrResult {
+        OcrResult::from_lines(texts.iter().enumerate().map(|(index, text)| line(text, index)).collect())
+    }
+
+    #[test]
+    fn collapse_cjk_spaces_japanese_and_korean() {
+        assert_eq!(collapse_cjk_spaces("日本 語 の テキスト"), "日本語のテキスト");
+        assert_eq!(collapse_cjk_spaces("한국 어 텍스트"), "한국어텍스트");
+        // 拉丁文字之间的空格保留
+        assert_eq!(collapse_cjk_spaces("Hello world カタ カナ"), "Hello world カタカナ");
+        assert_eq!(collapse_cjk_spaces("Open AI 한 국"), "Open AI 한국");
+        assert_eq!(collapse_cjk_spaces("中 文 ， 标 点"), "中文，标点");
+    }
+
+    #[test]
+    fn collapse_cjk_spaces_keeps_embedded_english() {
+        // 嵌入的英文单词两侧与中日韩文字之间的空格保留，只去除中日韩文字之间的空格
+        assert_eq!(collapse_cjk_spaces("これは test です"), "これは test です");
+        assert_eq!(collapse_cjk_spaces("こ れ は test で す"), "これは test です");
+        assert_eq!(collapse_cjk_spaces("これは a good test です"), "これは a good test です");
+        assert_eq!(collapse_cjk_spaces("이것은 test 입니다"), "이것은 test 입니다");
+        assert_eq!(collapse_cjk_spaces("ひらがな と カタカナ と 漢字"), "ひらがなとカタカナと漢字");
+        assert_eq!(collapse_cjk_spaces("  "), "  ");
+    }
+
+    #[test]
+    fn decode_image_data_formats() {
+        // 0xfb 0xff 0xfe 在标准字母表中编码为 "+//+"，在 URL 安全字母表中为 "-__-"
+        let bytes = vec![0xfb, 0xff, 0xfe, 0x89, b'P'];
+        let standard = general_purpose::STANDARD.encode(&bytes);
+        let url_safe = general_purpose::URL_SAFE.encode(&bytes);
+        assert!(standard.contains('+') && standard.ends_with('='));
+        assert!(url_safe.contains('-') && url_safe.ends_with('='));
+
+        for encoded in [
+            standard.clone(),
+            standard.trim_end_matches('=').to_string(),
+            url_safe.clone(),
+            url_safe.trim_end_matches('=').to_string(),
+            format!("data:image/png;base64,{}", standard),
+            format!("  data:image/jpeg;name=page.jpg;base64,{}\n", url_safe.trim_end_matches('=')),
+        ] {
+            assert_eq!(decode_image_data(&encoded), Ok(bytes.clone()), "{}", encoded);
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn decode_image_data_errors() {
+        assert!(decode_image_data("data:text/plain;base64,aGVsbG8=").unwrap_err().contains("MIME type"));
+        assert!(decode_image_data("data:image/png,rawdata").unwrap_err().contains("base64-encoded"));
+        assert!(decode_image_data("data:image/png;base64").unwrap_err().contains("missing ','"));
+        assert!(decode_image_data("not base64!").unwrap_err().contains("Failed to decode"));
+    }
+
+    #[test]
+    fn post_process_is_off_by_default() {
+        let result = post_process(result_from(&["中 文", "exam-", "ple"]), &Some(PostProcessOptions::default()));
+        assert_eq!(result.text, "中 文\nexam-\nple");
+    }
+
+    #[test]
+    fn post_process_applies_transforms_in_order() {
+        let options = PostProcessOptions {
+            collapse_cjk_spaces: true,
+            normalize_fullwidth: true,
+            dehyphenate: true,
+            collapse_blank_lines: true,
+            ..Default::default()
+        };
+        let result = post_process(result_from(&["中 文 ， ＯＣＲ", "", "", "exam-", "ple"]), &Some(options));
+        // 先去除中日韩文字和全角字符之间的空格，再转换为半角
+        assert_eq!(result.lines[0].text, "中文,OCR");
+        assert_eq!(result.text, "中文,OCR\n\nexample");
+        // 去连字符和合并空行只影响 text
+        assert_eq!(result.lines.len(), 5);
+    }
+
+    #[test]
+    fn post_process_dehyphenates_merged_paragraphs() {
+        let options = PostProcessOptions {
+            merge_paragraphs: true,
+            dehyphenate: true,
+            ..Default::default()
+        };
+        let result = post_process(result_from(&["An exam-", "ple of text."]), &Some(options));
+        assert_eq!(result.text, "An example of text.");
+    }
+
+    #[test]
+    fn post_process_skips_failures() {
+        let options = Some(PostProcessOptions {
+            collapse_blank_lines: true,
+            ..Default::default()
+        });
+        let result = post_process(OcrResult::failure(OcrError::EngineFailure, "failed".to_string()), &options);
+        assert!(!result.success);
+        assert_eq!(result.error_message.as_deref(), Some("failed"));
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn parses_supported_languages() {
+        let result = parse_supported_languages_output(
+            "Vision revision 3\nSUPPORTED_LANGUAGES_START\nen-US\n zh-Hans \n\nja-JP\nSUPPORTED_LANGUAGES_END\n",
+        );
+        assert!(result.success);
+        assert_eq!(result.languages, vec!["en-US", "zh-Hans", "ja-JP"]);
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn empty_supported_languages_is_an_error() {
+        let result = parse_supported_languages_output("SUPPORTED_LANGUAGES_START\n\nSUPPORTED_LANGUAGES_END\n");
+        assert!(!result.success);
+        assert!(result.languages.is_empty());
+        assert!(matches!(result.error_code, Some(OcrError::UnsupportedPlatform)));
+        assert!(result.error_message.unwrap().contains("macOS 10.15+"));
+
+        let result = parse_supported_languages_output("SUPPORTED_LANGUAGES_END\nSUPPORTED_LANGUAGES_START\n");
+        assert!(!result.success);
+        assert!(matches!(result.error_code, Some(OcrError::EngineFailure)));
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    #[test]
+    fn temp_file_is_removed_on_early_return() {
+        // 只有 PNG 文件头、无法解码的图片：写入临时文件后在解码时失败并提前返回
+        let truncated_png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let mut written_path = None;
+        let result = (|| -> std::result::Result<(), String> {
+            let temp_file = TempFile::write(truncated_png)?;
+            assert!(temp_file.path.exists());
+            assert_eq!(temp_file.path.extension().and_then(|e| e.to_str()), Some("png"));
+            written_path = Some(temp_file.path.clone());
+            image_ops::decode_image(&std::fs::read(&temp_file.path).map_err(|e| e.to_string())?)?;
+            Ok(())
+        })();
+        assert!(result.is_err());
+        assert!(!written_path.expect("temporary file was not written").exists());
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    #[test]
+    fn temp_file_rejects_unknown_formats() {
+        assert_eq!(TempFile::write(b"plain text").err().as_deref(), Some(UNSUPPORTED_IMAGE_FORMAT));
+    }
+
+    #[test]
+    fn jobs_are_removed_after_the_last_registration() {
+        let job_id = "jobs_are_removed_after_the_last_registration";
+        let request = OcrRequest {
+            job_id: Some(job_id.to_string()),
+            ..Default::default()
+        };
+        let first = OcrJob::register(job_id);
+        let second = OcrJob::register(job_id);
+        // 识别过程中另外持有的取消标志不影响任务的移除
+        let flag = cancel_flag(&request).unwrap();
+        assert!(Arc::ptr_eq(&flag, &first.cancelled) && Arc::ptr_eq(&flag, &second.cancelled));
+
+        drop(first);
+        assert!(OCR_JOBS.lock().unwrap().contains_key(job_id));
+        drop(second);
+        assert!(!OCR_JOBS.lock().unwrap().contains_key(job_id));
+        assert!(cancel_flag(&request).is_none());
+        drop(flag);
+
+        // 移除后重新注册得到新的、未取消的标志
+        let job = OcrJob::register(job_id);
+        assert!(!job.cancelled.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn page_panics_become_failed_results() {
+        let result = guard_page(|| panic!("corrupt page"));
+        assert!(!result.success);
+        assert_eq!(result.error_code, Some(OcrError::EngineFailure));
+        assert!(guard_page(|| result_from(&["ok"])).success);
+
+        let result = tauri::async_runtime::block_on(recognize_page(async { panic!("corrupt page") }));
+        assert_eq!(result.error_code, Some(OcrError::EngineFailure));
+        let result = tauri::async_runtime::block_on(recognize_page(async { result_from(&["ok"]) }));
+        assert_eq!(result.text, "ok");
+    }
+}
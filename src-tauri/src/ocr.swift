@@ -1,6 +1,22 @@
 import Cocoa
+import NaturalLanguage
 import Vision
 
+// 每一行识别结果，坐标以图片左上角为原点，按图片宽高归一化到 0~1
+struct RecognizedLine: Codable {
+    let text: String
+    let x: Double
+    let y: Double
+    let width: Double
+    let height: Double
+    let confidence: Float
+}
+
+// 错误和警告信息输出到 stderr，stdout 只输出识别结果
+func printError(_ message: String) {
+    FileHandle.standardError.write((message + "\n").data(using: .utf8)!)
+}
+
 // 获取系统支持的OCR语言
 func getSupportedRecognitionLanguages() -> [String] {
     // 正确的写法是调用方法并处理异常
@@ -8,105 +24,239 @@ func getSupportedRecognitionLanguages() -> [String] {
         let request = VNRecognizeTextRequest()
         return try request.supportedRecognitionLanguages()
     } catch {
-        print("Error getting supported languages: \(error)")
+        printError("Error getting supported languages: \(error)")
         return []
     }
 }
 
-// 从命令行参数获取文件路径和语言选项
-// 注意：CommandLine.arguments.count 至少为1（程序名本身）
-if CommandLine.arguments.count <= 1 {
-    // 如果没有参数，返回支持的语言列表
-    let languages = getSupportedRecognitionLanguages()
-    print("SUPPORTED_LANGUAGES_START")
-    for language in languages {
-        print(language)
-    }
-    print("SUPPORTED_LANGUAGES_END")
-    exit(0)
-}
+// 未指定语言或指定的语言都不支持时使用的默认语言：中英文
+let defaultLanguages = ["zh-Hans", "zh-Hant", "en-US"]
 
-// 必须至少有文件路径参数
-guard CommandLine.arguments.count > 1 else {
-    print("Usage: ocr <image_path> [language1,language2,...]")
-    exit(1)
+// 识别失败的原因
+struct OcrFailure: Error {
+    let message: String
 }
 
-let imagePath = CommandLine.arguments[1]
-let url = URL(fileURLWithPath: imagePath)
-
-// 获取语言参数（如果有）
-var languages: [String] = []
-if CommandLine.arguments.count > 2 {
-    let languagesString = CommandLine.arguments[2]
-    languages = languagesString.split(separator: ",").map { String($0) }
+// 过滤出系统支持的识别语言
+func resolveLanguages(_ request: VNRecognizeTextRequest, _ languages: [String]) -> [String] {
+    if languages.isEmpty {
+        return defaultLanguages
+    }
+    // 在设置语言之前，最好验证一下这些语言是否被支持
+    do {
+        let supportedLanguages = try request.supportedRecognitionLanguages()
+        let validLanguages = languages.filter { supportedLanguages.contains($0) }
+        if !validLanguages.isEmpty {
+            return validLanguages
+        }
+        printError("Warning: None of the provided languages are supported. Using default.")
+    } catch {
+        printError("Could not verify languages, using default. Error: \(error)")
+    }
+    return defaultLanguages
 }
 
-// 加载图像
-guard let image = NSImage(contentsOf: url) else {
-    print("Error: Could not load image from \(imagePath)")
-    exit(1)
+// 解析 --level 参数：fast 速度快，accurate 识别质量更好
+func parseRecognitionLevel(_ value: String) -> VNRequestTextRecognitionLevel? {
+    switch value {
+    case "fast":
+        return .fast
+    case "accurate":
+        return .accurate
+    default:
+        return nil
+    }
 }
 
-// 将 NSImage 转换为 CGImage
-guard let cgImage = image.cgImage(forProposedRect: nil, context: nil, hints: nil) else {
-    print("Error: Could not convert image to CGImage")
-    exit(1)
+// 从参数开头取出选项，遇到第一个不是选项的参数（图片路径）为止：
+// --level fast|accurate，未指定时使用 accurate；无法识别的取值直接报错退出
+// --lang <语言>，可以重复指定多个语言，未指定时使用默认语言
+func takeOptions(_ arguments: inout [String]) -> (level: VNRequestTextRecognitionLevel, languages: [String]) {
+    var level = VNRequestTextRecognitionLevel.accurate
+    var languages: [String] = []
+    while arguments.count >= 2 && (arguments[0] == "--level" || arguments[0] == "--lang") {
+        let (option, value) = (arguments[0], arguments[1])
+        arguments.removeFirst(2)
+        if option == "--lang" {
+            languages.append(value)
+        } else if let parsed = parseRecognitionLevel(value) {
+            level = parsed
+        } else {
+            printError("Error: Unknown recognition level \(value), expected fast or accurate")
+            exit(1)
+        }
+    }
+    return (level, languages)
 }
 
-// 创建 Vision 请求
-let request = VNRecognizeTextRequest { (request, error) in
-    if let error = error {
-        print("OCR Error: \(error)")
-        exit(1)
+// 识别一张图片中的文字；detectsLanguage 为 true 时由 Vision 自动检测语言，用于判断图片的语言
+func recognizeText(imagePath: String, languages: [String], level: VNRequestTextRecognitionLevel, detectsLanguage: Bool = false) throws -> [RecognizedLine] {
+    let url = URL(fileURLWithPath: imagePath)
+    
+    // 加载图像
+    guard let image = NSImage(contentsOf: url) else {
+        throw OcrFailure(message: "Error: Could not load image from \(imagePath)")
+    }
+    
+    // 将 NSImage 转换为 CGImage
+    guard let cgImage = image.cgImage(forProposedRect: nil, context: nil, hints: nil) else {
+        throw OcrFailure(message: "Error: Could not convert image to CGImage")
+    }
+    
+    // 创建 Vision 请求，设置识别级别和语言
+    let request = VNRecognizeTextRequest()
+    request.recognitionLevel = level
+    request.recognitionLanguages = resolveLanguages(request, languages)
+    if detectsLanguage {
+        if #available(macOS 13.0, *) {
+            request.automaticallyDetectsLanguage = true
+        } else if let supportedLanguages = try? request.supportedRecognitionLanguages() {
+            // 旧系统不能自动检测，改为同时使用所有支持的语言
+            request.recognitionLanguages = supportedLanguages
+        }
+    }
+    
+    // 执行请求
+    let handler = VNImageRequestHandler(cgImage: cgImage, options: [:])
+    do {
+        try handler.perform([request])
+    } catch {
+        throw OcrFailure(message: "Failed to perform OCR: \(error)")
     }
     
     guard let observations = request.results as? [VNRecognizedTextObservation] else {
-        print("Error: No text observations found")
-        exit(1)
+        throw OcrFailure(message: "Error: No text observations found")
     }
     
-    // 提取识别的文本
-    var recognizedText = ""
-    for observation in observations {
-        guard let topCandidate = observation.topCandidates(1).first else { continue }
-        recognizedText += topCandidate.string + "\n"
+    return observations.compactMap { observation in
+        guard let topCandidate = observation.topCandidates(1).first else { return nil }
+        // Vision 的 boundingBox 以左下角为原点，转换为以左上角为原点
+        let box = observation.boundingBox
+        return RecognizedLine(
+            text: topCandidate.string,
+            x: Double(box.minX),
+            y: Double(1 - box.maxY),
+            width: Double(box.width),
+            height: Double(box.height),
+            confidence: topCandidate.confidence
+        )
     }
+}
+
+// 检测语言时最多返回的候选语言数
+let maxDetectedLanguages = 5
+
+// 将 NaturalLanguage 的语言标识（例如 en）对应到 Vision 支持的识别语言（例如 en-US）
+func matchRecognitionLanguage(_ tag: String, _ supportedLanguages: [String]) -> String? {
+    if supportedLanguages.contains(tag) {
+        return tag
+    }
+    let primary = tag.split(separator: "-").first.map(String.init) ?? tag
+    return supportedLanguages.first { $0.split(separator: "-").first.map(String.init) == primary }
+}
+
+// 识别图片中的文字并判断其语言，按概率从高到低返回识别语言及其概率
+// fast 级别只支持拉丁字母语言，因此使用 accurate
+func detectLanguages(imagePath: String) throws -> [(String, Double)] {
+    let lines = try recognizeText(imagePath: imagePath, languages: [], level: .accurate, detectsLanguage: true)
+    
+    let recognizer = NLLanguageRecognizer()
+    recognizer.processString(lines.map { $0.text }.joined(separator: "\n"))
+    let hypotheses = recognizer.languageHypotheses(withMaximum: maxDetectedLanguages)
     
-    // 输出结果
-    print(recognizedText)
+    let supportedLanguages = getSupportedRecognitionLanguages()
+    var detected: [(String, Double)] = []
+    for (language, probability) in hypotheses.sorted(by: { $0.value > $1.value }) {
+        // 无法用于识别的语言不返回；多个语言对应到同一个识别语言时只保留概率最高的
+        guard let tag = matchRecognitionLanguage(language.rawValue, supportedLanguages),
+              !detected.contains(where: { $0.0 == tag }) else {
+            continue
+        }
+        detected.append((tag, probability))
+    }
+    return detected
+}
+
+// 每一行输出一个 JSON 对象
+func printLines(_ lines: [RecognizedLine]) {
+    let encoder = JSONEncoder()
+    for line in lines {
+        if let data = try? encoder.encode(line), let json = String(data: data, encoding: .utf8) {
+            print(json)
+        }
+    }
 }
 
-// 设置识别级别
-request.recognitionLevel = .accurate
+func failureMessage(_ error: Error) -> String {
+    return (error as? OcrFailure)?.message ?? "\(error)"
+}
 
-// 设置识别语言（如果提供了有效语言）
-if !languages.isEmpty {
-    // 在设置语言之前，最好验证一下这些语言是否被支持
+// 从命令行参数获取文件路径和语言选项
+// 注意：CommandLine.arguments.count 至少为1（程序名本身）
+let arguments = Array(CommandLine.arguments.dropFirst())
+
+if arguments.isEmpty {
+    // 如果没有参数，返回支持的语言列表
+    let languages = getSupportedRecognitionLanguages()
+    print("SUPPORTED_LANGUAGES_START")
+    for language in languages {
+        print(language)
+    }
+    print("SUPPORTED_LANGUAGES_END")
+    exit(0)
+}
+
+if arguments[0] == "--batch" {
+    // 批量模式：ocr --batch [--lang language]... [--level fast|accurate] <image_path>...
+    // 只启动一次进程识别所有图片，每张图片的结果以 IMAGE_START / IMAGE_END 包围，
+    // 失败的图片输出一行 IMAGE_ERROR <错误信息>，不影响其他图片
+    var imagePaths = Array(arguments.dropFirst())
+    let (level, languages) = takeOptions(&imagePaths)
+    
+    for imagePath in imagePaths {
+        do {
+            let lines = try recognizeText(imagePath: imagePath, languages: languages, level: level)
+            print("IMAGE_START")
+            printLines(lines)
+            print("IMAGE_END")
+        } catch {
+            // 错误信息只占一行
+            let message = failureMessage(error).replacingOccurrences(of: "\n", with: " ")
+            print("IMAGE_ERROR \(message)")
+        }
+    }
+    exit(0)
+}
+
+if arguments[0] == "--detect-language" {
+    // 语言检测模式：ocr --detect-language <image_path>
+    // 每行输出一个识别语言及其概率（以制表符分隔），按概率从高到低排列；没有识别出文字时不输出
+    guard arguments.count >= 2 else {
+        printError("Error: Missing image path")
+        exit(1)
+    }
     do {
-        let supportedLanguages = try request.supportedRecognitionLanguages()
-        let validLanguages = languages.filter { supportedLanguages.contains($0) }
-        if !validLanguages.isEmpty {
-            request.recognitionLanguages = validLanguages
-        } else {
-            print("Warning: None of the provided languages are supported. Using default.")
-            request.recognitionLanguages = ["zh-Hans", "zh-Hant", "en-US"]
+        for (tag, probability) in try detectLanguages(imagePath: arguments[1]) {
+            print("\(tag)\t\(probability)")
         }
     } catch {
-        print("Could not verify languages, using default. Error: \(error)")
-        request.recognitionLanguages = ["zh-Hans", "zh-Hant", "en-US"]
+        printError(failureMessage(error))
+        exit(1)
     }
-} else {
-    // 默认支持中英文
-    request.recognitionLanguages = ["zh-Hans", "zh-Hant", "en-US"]
+    exit(0)
 }
 
+// 单张模式：ocr [--level fast|accurate] [--lang language]... <image_path>
+var singleArguments = arguments
+let (level, languages) = takeOptions(&singleArguments)
+guard let imagePath = singleArguments.first else {
+    printError("Error: Missing image path")
+    exit(1)
+}
 
-// 执行请求
-let handler = VNImageRequestHandler(cgImage: cgImage, options: [:])
 do {
-    try handler.perform([request])
+    printLines(try recognizeText(imagePath: imagePath, languages: languages, level: level))
 } catch {
-    print("Failed to perform OCR: \(error)")
+    printError(failureMessage(error))
     exit(1)
-}
\ No newline at end of file
+}
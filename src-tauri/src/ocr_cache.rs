@@ -0,0 +1,168 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::command;
+
+use crate::ocr::{OcrResult, RecognitionLevel};
+
+// 未设置环境变量 OCR_CACHE_CAPACITY 时最多缓存的识别结果数
+const DEFAULT_CAPACITY: usize = 64;
+
+/// 缓存键：图像内容的 SHA-256，以及影响识别结果的请求参数
+/// 后处理选项在读取缓存之后才应用，不属于缓存键
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    image_hash: [u8; 32],
+    languages: Option<Vec<String>>,
+    recognition_level: Option<RecognitionLevel>,
+}
+
+impl CacheKey {
+    pub fn new(image_data: &[u8], languages: &Option<Vec<String>>, recognition_level: Option<RecognitionLevel>) -> Self {
+        Self::with_hash(Sha256::digest(image_data).into(), languages, recognition_level)
+    }
+
+    /// 以流式方式计算图片文件的 SHA-256，不把整个文件读入内存；与 new 对同样内容得到相同的键
+    pub fn from_file(
+        path: &Path,
+        languages: &Option<Vec<String>>,
+        recognition_level: Option<RecognitionLevel>,
+    ) -> std::io::Result<Self> {
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut BufReader::new(File::open(path)?), &mut hasher)?;
+        Ok(Self::with_hash(hasher.finalize().into(), languages, recognition_level))
+    }
+
+    fn with_hash(
+        image_hash: [u8; 32],
+        languages: &Option<Vec<String>>,
+        recognition_level: Option<RecognitionLevel>,
+    ) -> Self {
+        CacheKey {
+            image_hash,
+            // 未指定语言和指定空列表的效果相同
+            languages: languages.clone().filter(|languages| !languages.is_empty()),
+            recognition_level,
+        }
+    }
+}
+
+/// 按最近使用顺序淘汰的识别结果缓存，最近使用的在队尾
+struct OcrCache {
+    entries: VecDeque<(CacheKey, OcrResult)>,
+    capacity: usize,
+    hits: u64,
+    misses: u64,
+}
+
+lazy_static::lazy_static! {
+    static ref OCR_CACHE: Mutex<OcrCache> = Mutex::new(OcrCache {
+        entries: VecDeque::new(),
+        capacity: capacity_from_env(),
+        hits: 0,
+        misses: 0,
+    });
+}
+
+/// 缓存容量可以用环境变量 OCR_CACHE_CAPACITY 设置，设为 0 时不缓存
+fn capacity_from_env() -> usize {
+    std::env::var("OCR_CACHE_CAPACITY")
+        .ok()
+        .and_then(|capacity| capacity.trim().parse().ok())
+        .unwrap_or(DEFAULT_CAPACITY)
+}
+
+/// 查找缓存的识别结果，命中时将其移到队尾
+pub fn get(key: &CacheKey) -> Option<OcrResult> {
+    let mut cache = OCR_CACHE.lock().unwrap();
+    match cache.entries.iter().position(|(entry_key, _)| entry_key == key) {
+        Some(position) => {
+            cache.hits += 1;
+            let entry = cache.entries.remove(position)?;
+            let result = entry.1.clone();
+            cache.entries.push_back(entry);
+            Some(result)
+        }
+        None => {
+            cache.misses += 1;
+            None
+        }
+    }
+}
+
+/// 缓存识别结果；失败的结果不缓存，以便重试
+pub fn insert(key: CacheKey, result: &OcrResult) {
+    if !result.success {
+        return;
+    }
+
+    let mut cache = OCR_CACHE.lock().unwrap();
+    if cache.capacity == 0 {
+        return;
+    }
+    cache.entries.retain(|(entry_key, _)| *entry_key != key);
+    while cache.entries.len() >= cache.capacity {
+        cache.entries.pop_front();
+    }
+    cache.entries.push_back((key, result.clone()));
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OcrCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+/// 清空缓存的识别结果，命中和未命中次数一并清零
+#[command]
+pub async fn clear_ocr_cache() {
+    let mut cache = OCR_CACHE.lock().unwrap();
+    cache.entries.clear();
+    cache.hits = 0;
+    cache.misses = 0;
+}
+
+#[command]
+pub async fn ocr_cache_stats() -> OcrCacheStats {
+    let cache = OCR_CACHE.lock().unwrap();
+    OcrCacheStats {
+        hits: cache.hits,
+        misses: cache.misses,
+        entries: cache.entries.len(),
+        capacity: cache.capacity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_key_matches_in_memory_key() {
+        let image_data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let path = std::env::temp_dir().join(format!("ocr_cache_test_{}.bin", uuid::Uuid::new_v4()));
+        std::fs::write(&path, &image_data).unwrap();
+        let languages = Some(vec!["en-US".to_string()]);
+
+        let from_file = CacheKey::from_file(&path, &languages, None);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(from_file.unwrap(), CacheKey::new(&image_data, &languages, None));
+        assert_ne!(
+            CacheKey::new(&image_data, &languages, None),
+            CacheKey::new(&image_data[1..], &languages, None)
+        );
+        assert!(CacheKey::from_file(&path, &languages, None).is_err());
+    }
+
+    #[test]
+    fn empty_language_list_matches_unspecified() {
+        assert_eq!(CacheKey::new(b"image", &Some(vec![]), None), CacheKey::new(b"image", &None, None));
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::command;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+lazy_static::lazy_static! {
+    // 同时进行的OCR识别数量上限，避免前端一次识别大量页面时同时启动过多的OCR进程
+    static ref OCR_LIMIT: Mutex<usize> = Mutex::new(default_limit());
+    // 可用的识别许可；等待是异步的，不占用运行时的工作线程，并按请求的先后顺序分配
+    static ref OCR_PERMITS: Arc<Semaphore> = Arc::new(Semaphore::new(default_limit()));
+}
+
+/// 默认上限为CPU核心数（标准库只能取得逻辑核心数）
+fn default_limit() -> usize {
+    std::thread::available_parallelism()
+        .map(|parallelism| parallelism.get())
+        .unwrap_or(1)
+}
+
+/// 一次识别的许可，drop 时归还，无论识别从哪个分支返回（包括失败和超时）都不会遗漏
+pub struct OcrPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// 等待取得一次识别的许可
+pub async fn acquire() -> OcrPermit {
+    let permit = OCR_PERMITS
+        .clone()
+        .acquire_owned()
+        .await
+        .expect("OCR permit semaphore is never closed");
+    OcrPermit { _permit: permit }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OcrConcurrencyResult {
+    pub limit: usize,
+    pub success: bool,
+    pub error_message: Option<String>,
+}
+
+/// 设置同时进行的OCR识别数量上限；调低时正在进行的识别不受影响，之后的识别等到数量低于上限才开始
+/// 批量识别（extract_text_batch）整体只占用一个许可；语言检测和 ocr_pdf_page 的页面渲染也各占用一个许可
+#[command]
+pub async fn set_ocr_concurrency(n: usize) -> OcrConcurrencyResult {
+    let mut limit = OCR_LIMIT.lock().unwrap();
+    if n == 0 {
+        return OcrConcurrencyResult {
+            limit: *limit,
+            success: false,
+            error_message: Some("OCR concurrency must be at least 1".to_string()),
+        };
+    }
+
+    if n > *limit {
+        OCR_PERMITS.add_permits(n - *limit);
+    } else if n < *limit {
+        // 收回多出的许可：排在已经等待的识别之后取得，取得后丢弃
+        let excess = u32::try_from(*limit - n).unwrap_or(u32::MAX);
+        let permits = OCR_PERMITS.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Ok(permits) = permits.acquire_many_owned(excess).await {
+                permits.forget();
+            }
+        });
+    }
+    *limit = n;
+    OcrConcurrencyResult {
+        limit: n,
+        success: true,
+        error_message: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 等待 set_ocr_concurrency 在后台收回多出的许可
+    fn wait_for_available_permits(expected: usize) {
+        for _ in 0..200 {
+            if OCR_PERMITS.available_permits() == expected {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(OCR_PERMITS.available_permits(), expected);
+    }
+
+    #[test]
+    fn concurrency_limit_adjusts_permits() {
+        tauri::async_runtime::block_on(async {
+            let initial = *OCR_LIMIT.lock().unwrap();
+            assert!(!set_ocr_concurrency(0).await.success);
+            assert_eq!(*OCR_LIMIT.lock().unwrap(), initial);
+
+            assert!(set_ocr_concurrency(initial + 2).await.success);
+            wait_for_available_permits(initial + 2);
+
+            // 调低上限时正在进行的识别不受影响，归还许可后才低于上限
+            let permit = acquire().await;
+            assert!(set_ocr_concurrency(1).await.success);
+            wait_for_available_permits(0);
+            drop(permit);
+            wait_for_available_permits(1);
+
+            let permit = acquire().await;
+            assert_eq!(OCR_PERMITS.available_permits(), 0);
+            assert!(set_ocr_concurrency(2).await.success);
+            wait_for_available_permits(1);
+            drop(permit);
+            wait_for_available_permits(2);
+        });
+    }
+}
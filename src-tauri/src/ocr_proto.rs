@@ -0,0 +1,221 @@
+use prost::Message;
+use tauri::command;
+
+use crate::ocr::{OcrError, OcrLine, OcrResult};
+
+/// 由 proto/ocr.proto 生成的消息定义（见 build.rs），修改 OcrResult 的字段时只需同步更新 .proto 文件和下面的转换
+mod proto {
+    include!(concat!(env!("OUT_DIR"), "/ocr.rs"));
+}
+
+use proto::{OcrErrorCode, OcrLine as OcrLineMessage, OcrResult as OcrResultMessage};
+
+impl From<OcrError> for OcrErrorCode {
+    fn from(error: OcrError) -> Self {
+        match error {
+            OcrError::InvalidRequest => OcrErrorCode::InvalidRequest,
+            OcrError::ImageUnreadable => OcrErrorCode::ImageUnreadable,
+            OcrError::DecodeFailed => OcrErrorCode::DecodeFailed,
+            OcrError::BinaryNotFound => OcrErrorCode::BinaryNotFound,
+            OcrError::UnsupportedLanguage => OcrErrorCode::UnsupportedLanguage,
+            OcrError::EngineFailure => OcrErrorCode::EngineFailure,
+            OcrError::Timeout => OcrErrorCode::Timeout,
+            OcrError::UnsupportedPlatform => OcrErrorCode::UnsupportedPlatform,
+            OcrError::Cancelled => OcrErrorCode::Cancelled,
+        }
+    }
+}
+
+impl OcrErrorCode {
+    /// Unspecified 表示没有错误分类
+    fn to_ocr_error(self) -> Option<OcrError> {
+        match self {
+            OcrErrorCode::Unspecified => None,
+            OcrErrorCode::InvalidRequest => Some(OcrError::InvalidRequest),
+            OcrErrorCode::ImageUnreadable => Some(OcrError::ImageUnreadable),
+            OcrErrorCode::DecodeFailed => Some(OcrError::DecodeFailed),
+            OcrErrorCode::BinaryNotFound => Some(OcrError::BinaryNotFound),
+            OcrErrorCode::UnsupportedLanguage => Some(OcrError::UnsupportedLanguage),
+            OcrErrorCode::EngineFailure => Some(OcrError::EngineFailure),
+            OcrErrorCode::Timeout => Some(OcrError::Timeout),
+            OcrErrorCode::UnsupportedPlatform => Some(OcrError::UnsupportedPlatform),
+            OcrErrorCode::Cancelled => Some(OcrError::Cancelled),
+        }
+    }
+}
+
+impl From<&OcrLine> for OcrLineMessage {
+    fn from(line: &OcrLine) -> Self {
+        OcrLineMessage {
+            text: line.text.clone(),
+            x: line.x,
+            y: line.y,
+            width: line.width,
+            height: line.height,
+            confidence: line.confidence,
+            reading_order_confidence: line.reading_order_confidence,
+        }
+    }
+}
+
+impl From<OcrLineMessage> for OcrLine {
+    fn from(message: OcrLineMessage) -> Self {
+        OcrLine {
+            text: message.text,
+            x: message.x,
+            y: message.y,
+            width: message.width,
+            height: message.height,
+            confidence: message.confidence,
+            reading_order_confidence: message.reading_order_confidence,
+        }
+    }
+}
+
+impl From<&OcrResult> for OcrResultMessage {
+    fn from(result: &OcrResult) -> Self {
+        OcrResultMessage {
+            text: result.text.clone(),
+            success: result.success,
+            error_message: result.error_message.clone(),
+            lines: result.lines.iter().map(OcrLineMessage::from).collect(),
+            applied_transform: result.applied_transform.map(Vec::from).unwrap_or_default(),
+            error_code: result.error_code.map(|code| OcrErrorCode::from(code) as i32),
+            confidence: result.confidence,
+            applied_rotation: result.applied_rotation.map(u32::from),
+        }
+    }
+}
+
+impl From<OcrResultMessage> for OcrResult {
+    fn from(message: OcrResultMessage) -> Self {
+        OcrResult {
+            text: message.text,
+            success: message.success,
+            error_message: message.error_message,
+            // 无法识别的错误码视为没有错误分类
+            error_code: message
+                .error_code
+                .and_then(|code| OcrErrorCode::try_from(code).ok())
+                .and_then(OcrErrorCode::to_ocr_error),
+            lines: message.lines.into_iter().map(OcrLine::from).collect(),
+            // 长度不是 9 的矩阵视为没有变换
+            applied_transform: message.applied_transform.try_into().ok(),
+            confidence: message.confidence,
+            // 超出 u16 范围的角度视为没有旋转信息
+            applied_rotation: message.applied_rotation.and_then(|rotation| u16::try_from(rotation).ok()),
+        }
+    }
+}
+
+impl OcrResult {
+    /// 将识别结果编码为 protobuf 字节
+    pub fn to_protobuf(&self) -> Vec<u8> {
+        OcrResultMessage::from(self).encode_to_vec()
+    }
+
+    /// 从 protobuf 字节解码识别结果
+    pub fn from_protobuf(bytes: &[u8]) -> Result<Self, prost::DecodeError> {
+        OcrResultMessage::decode(bytes).map(OcrResult::from)
+    }
+}
+
+#[command]
+pub async fn ocr_result_to_protobuf(result: OcrResult) -> Vec<u8> {
+    result.to_protobuf()
+}
+
+#[command]
+pub async fn ocr_result_from_protobuf(bytes: Vec<u8>) -> OcrResult {
+    match OcrResult::from_protobuf(&bytes) {
+        Ok(result) => result,
+        Err(e) => OcrResult::failure(OcrError::DecodeFailed, format!("Failed to decode protobuf OCR result: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result() -> OcrResult {
+        let lines = vec![
+            OcrLine {
+                text: "第一行".to_string(),
+                x: 0.1,
+                y: 0.2,
+                width: 0.5,
+                height: 0.05,
+                confidence: Some(0.75),
+                reading_order_confidence: 1.0,
+            },
+            OcrLine {
+                text: "second line".to_string(),
+                x: 0.1,
+                y: 0.3,
+                width: 0.25,
+                height: 0.05,
+                confidence: None,
+                reading_order_confidence: 0.5,
+            },
+        ];
+        OcrResult {
+            text: "第一行\nsecond line".to_string(),
+            success: true,
+            error_message: None,
+            error_code: None,
+            lines,
+            applied_transform: Some([0.0, -1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0]),
+            confidence: Some(0.75),
+            applied_rotation: Some(90),
+        }
+    }
+
+    /// 经过 protobuf 编码再解码后，JSON 形式与原结果完全一致
+    fn assert_round_trip(result: &OcrResult) {
+        let decoded = OcrResult::from_protobuf(&result.to_protobuf()).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(result).unwrap());
+    }
+
+    #[test]
+    fn round_trip_keeps_every_json_field() {
+        let result = sample_result();
+        assert_round_trip(&result);
+
+        // OcrResult 或 OcrLine 增加字段时这里会失败：需要同时在 proto/ocr.proto 中增加字段，并在 sample_result 中赋值
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json.as_object().unwrap().len(), 8);
+        assert_eq!(json["lines"][0].as_object().unwrap().len(), 7);
+    }
+
+    #[test]
+    fn round_trip_keeps_error_codes() {
+        for code in [
+            OcrError::InvalidRequest,
+            OcrError::ImageUnreadable,
+            OcrError::DecodeFailed,
+            OcrError::BinaryNotFound,
+            OcrError::UnsupportedLanguage,
+            OcrError::EngineFailure,
+            OcrError::Timeout,
+            OcrError::UnsupportedPlatform,
+            OcrError::Cancelled,
+        ] {
+            assert_round_trip(&OcrResult::failure(code, format!("{:?}", code)));
+        }
+    }
+
+    #[test]
+    fn unknown_values_decode_as_absent() {
+        let message = OcrResultMessage {
+            success: true,
+            error_code: Some(100),
+            applied_transform: vec![1.0, 0.0],
+            applied_rotation: Some(u32::MAX),
+            ..Default::default()
+        };
+        let result = OcrResult::from_protobuf(&message.encode_to_vec()).unwrap();
+        assert!(result.error_code.is_none());
+        assert!(result.applied_transform.is_none());
+        assert!(result.applied_rotation.is_none());
+    }
+}
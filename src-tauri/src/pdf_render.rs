@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use pdfium_render::prelude::{PdfPageIndex, PdfRenderConfig, Pdfium};
+
+use crate::image_ops;
+use crate::ocr::OcrError;
+
+/// 未指定 dpi 时的渲染分辨率，OCR 在 300 DPI 左右效果较好
+pub const DEFAULT_DPI: u32 = 300;
+/// 最大渲染分辨率，避免大幅面的页面渲染出过大的图片
+const MAX_DPI: u32 = 1200;
+// PDF 的页面尺寸以点（1/72 英寸）为单位
+const POINTS_PER_INCH: f32 = 72.0;
+
+const PDFIUM_NOT_FOUND: &str = "PDFium library was not found. Download it from https://github.com/bblanchon/pdfium-binaries and place it next to the application, in a system library directory, or in the directory given by PDFIUM_LIBRARY_PATH";
+
+/// 渲染得到的PNG图像数据，失败时为错误分类和错误信息
+type RenderResult = std::result::Result<Vec<u8>, (OcrError, String)>;
+
+lazy_static::lazy_static! {
+    // 已加载的 PDFium；加载失败时不缓存，安装动态库后不必重启应用
+    static ref PDFIUM: Mutex<Option<Arc<Pdfium>>> = Mutex::new(None);
+}
+
+/// 加载 PDFium 动态库，依次查找环境变量 PDFIUM_LIBRARY_PATH 指定的目录、可执行文件所在的目录和系统库
+fn load_pdfium() -> std::result::Result<Arc<Pdfium>, (OcrError, String)> {
+    let mut pdfium = PDFIUM.lock().unwrap();
+    if let Some(pdfium) = pdfium.as_ref() {
+        return Ok(pdfium.clone());
+    }
+
+    let mut directories: Vec<PathBuf> = Vec::new();
+    if let Some(dir) = std::env::var_os("PDFIUM_LIBRARY_PATH").filter(|dir| !dir.is_empty()) {
+        directories.push(dir.into());
+    }
+    if let Some(dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) {
+        directories.push(dir);
+    }
+    let bindings = directories
+        .iter()
+        .find_map(|dir| Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(dir)).ok())
+        .or_else(|| Pdfium::bind_to_system_library().ok())
+        .ok_or_else(|| (OcrError::BinaryNotFound, PDFIUM_NOT_FOUND.to_string()))?;
+
+    let loaded = Arc::new(Pdfium::new(bindings));
+    *pdfium = Some(loaded.clone());
+    Ok(loaded)
+}
+
+/// 将PDF文档的一页渲染为PNG图像数据，page 从 1 开始（与前端的页码一致）
+/// 各平台都使用 PDFium 渲染，白色背景，已考虑页面的旋转；渲染会阻塞，应在阻塞线程池中调用
+pub fn render_page(pdf_path: &str, page: u32, dpi: u32) -> RenderResult {
+    if dpi == 0 || dpi > MAX_DPI {
+        return Err((OcrError::InvalidRequest, format!("dpi must be between 1 and {}", MAX_DPI)));
+    }
+    if page == 0 {
+        return Err((OcrError::InvalidRequest, "Page numbers start at 1".to_string()));
+    }
+
+    let path = Path::new(pdf_path);
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => {}
+        Ok(_) => return Err((OcrError::ImageUnreadable, format!("PDF path is not a file: {}", pdf_path))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err((OcrError::ImageUnreadable, format!("PDF file not found: {}", pdf_path)));
+        }
+        Err(e) => return Err((OcrError::ImageUnreadable, format!("Cannot access PDF file {}: {}", pdf_path, e))),
+    }
+
+    let pdfium = load_pdfium()?;
+    let document = pdfium
+        .load_pdf_from_file(path, None)
+        .map_err(|e| (OcrError::DecodeFailed, format!("Failed to load PDF document: {}", e)))?;
+
+    let pages = document.pages();
+    let page_count = u32::from(pages.len());
+    if page > page_count {
+        return Err((
+            OcrError::InvalidRequest,
+            format!("Page {} is out of range: the document has {} pages", page, page_count),
+        ));
+    }
+    // page 不超过页数，页数为 u16，转换不会溢出
+    let pdf_page = pages
+        .get((page - 1) as PdfPageIndex)
+        .map_err(|e| (OcrError::DecodeFailed, format!("Failed to get PDF page {}: {}", page, e)))?;
+
+    let config = PdfRenderConfig::new().scale_page_by_factor(dpi as f32 / POINTS_PER_INCH);
+    let bitmap = pdf_page
+        .render_with_config(&config)
+        .map_err(|e| (OcrError::EngineFailure, format!("Failed to render PDF page {}: {}", page, e)))?;
+    image_ops::encode_png(&bitmap.as_image()).map_err(|e| (OcrError::EngineFailure, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_code(result: RenderResult) -> OcrError {
+        result.unwrap_err().0
+    }
+
+    #[test]
+    fn rejects_invalid_requests_before_loading_pdfium() {
+        let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        assert_eq!(error_code(render_page(manifest, 1, 0)), OcrError::InvalidRequest);
+        assert_eq!(error_code(render_page(manifest, 1, MAX_DPI + 1)), OcrError::InvalidRequest);
+        assert_eq!(error_code(render_page(manifest, 0, DEFAULT_DPI)), OcrError::InvalidRequest);
+        assert_eq!(error_code(render_page("/nonexistent/document.pdf", 1, DEFAULT_DPI)), OcrError::ImageUnreadable);
+        assert_eq!(error_code(render_page(env!("CARGO_MANIFEST_DIR"), 1, DEFAULT_DPI)), OcrError::ImageUnreadable);
+    }
+}
@@ -0,0 +1,477 @@
+// 支持的 SSML 子集：speak（根元素）、break、emphasis、prosody（仅 rate 属性，其他属性忽略）
+// 另外支持把 say 风格的 [[slnc N]]、[[emph +]] 命令转换为同样的结构，见 Ssml::from_say_markup
+
+// 未指定 time 时各 strength 对应的停顿毫秒数
+const BREAK_STRENGTHS: &[(&str, u32)] = &[
+    ("none", 0),
+    ("x-weak", 100),
+    ("weak", 250),
+    ("medium", 500),
+    ("strong", 1000),
+    ("x-strong", 2000),
+];
+// prosody rate 的关键字对应的语速倍数
+const RATE_KEYWORDS: &[(&str, f32)] = &[
+    ("x-slow", 0.5),
+    ("slow", 0.75),
+    ("medium", 1.0),
+    ("default", 1.0),
+    ("fast", 1.5),
+    ("x-fast", 2.0),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SsmlNode {
+    Text(String),
+    Break(u32), // 停顿的毫秒数
+    Emphasis(Vec<SsmlNode>),
+    Prosody {
+        rate: Option<ProsodyRate>,
+        children: Vec<SsmlNode>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProsodyRate {
+    pub value: String,   // 原始的 rate 属性值，重新生成 SSML 时原样写回
+    pub multiplier: f32, // 相对于外层语速的倍数
+}
+
+/// 解析后的 SSML 文档，根元素 speak 不保留
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ssml {
+    pub nodes: Vec<SsmlNode>,
+}
+
+enum Token {
+    Text(String),
+    Open {
+        name: String,
+        attributes: Vec<(String, String)>,
+        self_closing: bool,
+    },
+    Close(String),
+}
+
+impl Ssml {
+    /// 解析 SSML；格式错误或含有不支持的元素时返回错误，而不是把标签当作文字朗读
+    pub fn parse(input: &str) -> Result<Ssml, String> {
+        let mut tokens = tokenize(input)?.into_iter();
+        let nodes = parse_nodes(&mut tokens, None)?;
+        Ok(Ssml { nodes })
+    }
+
+    /// 将文本中 say 风格的嵌入命令转换为 SSML，各平台的效果一致：
+    /// - [[slnc N]] 转换为 N 毫秒的停顿
+    /// - [[emph +]] 强调其后的一个单词，[[emph -]] 忽略
+    /// - 其他 [[...]] 不执行，作为普通文字保留
+    pub fn from_say_markup(text: &str) -> Ssml {
+        let mut nodes = Vec::new();
+        let mut pending = String::new(); // 尚未加入 nodes 的文字
+        let mut emphasize_next = false;
+        let mut rest = text;
+        
+        while let Some(start) = rest.find("[[") {
+            let end = match rest[start..].find("]]") {
+                Some(end) => start + end,
+                None => break,
+            };
+            push_say_text(&rest[..start], &mut nodes, &mut pending, &mut emphasize_next);
+            
+            let command: Vec<&str> = rest[start + 2..end].split_whitespace().collect();
+            match command.as_slice() {
+                ["slnc", ms] => match ms.parse::<u32>() {
+                    Ok(ms) => {
+                        flush_text(&mut nodes, &mut pending);
+                        nodes.push(SsmlNode::Break(ms));
+                    }
+                    Err(_) => pending.push_str(&rest[start..end + 2]),
+                },
+                ["emph", "+"] => emphasize_next = true,
+                ["emph", "-"] => {}
+                _ => pending.push_str(&rest[start..end + 2]),
+            }
+            rest = &rest[end + 2..];
+        }
+        
+        push_say_text(rest, &mut nodes, &mut pending, &mut emphasize_next);
+        flush_text(&mut nodes, &mut pending);
+        Ssml { nodes }
+    }
+
+    /// 对每一段文字做替换（例如发音词典），标签结构保持不变
+    /// macOS 上在转换为 say 的嵌入命令时替换，不使用此方法
+    #[cfg(any(not(target_os = "macos"), test))]
+    pub fn map_text(self, f: &dyn Fn(&str) -> String) -> Ssml {
+        Ssml {
+            nodes: map_nodes(self.nodes, f),
+        }
+    }
+
+    /// 重新生成完整的 SSML 文档；language 为 xml:lang，WinRT 要求必须提供
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    pub fn to_document(&self, language: Option<&str>) -> String {
+        let mut output = String::from(r#"<speak version="1.0" xmlns="http://www.w3.org/2001/10/synthesis""#);
+        if let Some(language) = language {
+            output.push_str(&format!(r#" xml:lang="{}""#, escape(language)));
+        }
+        output.push('>');
+        write_nodes(&self.nodes, &mut output);
+        output.push_str("</speak>");
+        output
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            let end = after.find("-->").ok_or("Unterminated comment")?;
+            rest = &after[end + 3..];
+        } else if let Some(after) = rest.strip_prefix("<?") {
+            let end = after.find("?>").ok_or("Unterminated XML declaration")?;
+            rest = &after[end + 2..];
+        } else if let Some(after) = rest.strip_prefix('<') {
+            let end = after.find('>').ok_or("Unterminated tag")?;
+            tokens.push(parse_tag(&after[..end])?);
+            rest = &after[end + 1..];
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            tokens.push(Token::Text(unescape(&rest[..end])?));
+            rest = &rest[end..];
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// 解析尖括号之间的内容
+fn parse_tag(content: &str) -> Result<Token, String> {
+    if let Some(name) = content.strip_prefix('/') {
+        return Ok(Token::Close(name.trim().to_string()));
+    }
+
+    let (content, self_closing) = match content.strip_suffix('/') {
+        Some(content) => (content.trim(), true),
+        None => (content.trim(), false),
+    };
+    let name_end = content.find(char::is_whitespace).unwrap_or(content.len());
+    let name = &content[..name_end];
+    if name.is_empty() {
+        return Err("Empty tag name".to_string());
+    }
+
+    let mut attributes = Vec::new();
+    let mut rest = content[name_end..].trim_start();
+    while !rest.is_empty() {
+        let equals = rest.find('=').ok_or_else(|| format!("Malformed attribute in <{}>", name))?;
+        let key = rest[..equals].trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(format!("Malformed attribute in <{}>", name));
+        }
+        let after = rest[equals + 1..].trim_start();
+        let quote = after
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+            .ok_or_else(|| format!("Attribute {} in <{}> must be quoted", key, name))?;
+        let value_end = after[1..]
+            .find(quote)
+            .ok_or_else(|| format!("Unterminated attribute {} in <{}>", key, name))?;
+        attributes.push((key.to_string(), unescape(&after[1..1 + value_end])?));
+        rest = after[value_end + 2..].trim_start();
+    }
+
+    Ok(Token::Open {
+        name: name.to_string(),
+        attributes,
+        self_closing,
+    })
+}
+
+/// 按嵌套结构解析标签，直到遇到 parent 的结束标签
+fn parse_nodes(tokens: &mut std::vec::IntoIter<Token>, parent: Option<&str>) -> Result<Vec<SsmlNode>, String> {
+    let mut nodes = Vec::new();
+
+    while let Some(token) = tokens.next() {
+        let (name, attributes, self_closing) = match token {
+            Token::Text(text) => {
+                nodes.push(SsmlNode::Text(text));
+                continue;
+            }
+            Token::Close(name) if Some(name.as_str()) == parent => return Ok(nodes),
+            Token::Close(name) => return Err(format!("Unexpected closing tag </{}>", name)),
+            Token::Open {
+                name,
+                attributes,
+                self_closing,
+            } => (name, attributes, self_closing),
+        };
+
+        let attribute = |key: &str| {
+            attributes
+                .iter()
+                .find(|(attribute, _)| attribute == key)
+                .map(|(_, value)| value.as_str())
+        };
+        let children = if self_closing {
+            vec![]
+        } else {
+            parse_nodes(tokens, Some(&name))?
+        };
+
+        match name.as_str() {
+            "speak" if parent.is_none() => nodes.extend(children),
+            "break" => {
+                let is_empty = children
+                    .iter()
+                    .all(|child| matches!(child, SsmlNode::Text(text) if text.trim().is_empty()));
+                if !is_empty {
+                    return Err("<break> must be empty".to_string());
+                }
+                nodes.push(SsmlNode::Break(break_duration(attribute("time"), attribute("strength"))?));
+            }
+            "emphasis" => nodes.push(SsmlNode::Emphasis(children)),
+            "prosody" => {
+                let rate = attribute("rate").map(parse_rate).transpose()?;
+                nodes.push(SsmlNode::Prosody { rate, children });
+            }
+            _ => {
+                return Err(format!(
+                    "Unsupported SSML element <{}>; supported elements are speak, break, emphasis and prosody",
+                    name
+                ));
+            }
+        }
+    }
+
+    match parent {
+        Some(parent) => Err(format!("Missing closing tag </{}>", parent)),
+        None => Ok(nodes),
+    }
+}
+
+/// break 的停顿时长：time 为 "500ms" 或 "1.5s"，未指定 time 时按 strength（默认 medium）
+fn break_duration(time: Option<&str>, strength: Option<&str>) -> Result<u32, String> {
+    if let Some(time) = time {
+        let time = time.trim();
+        let (number, scale) = match time.strip_suffix("ms") {
+            Some(number) => (number, 1.0),
+            None => match time.strip_suffix('s') {
+                Some(number) => (number, 1000.0),
+                None => return Err(format!("Invalid break time {:?}: expected e.g. 500ms or 1s", time)),
+            },
+        };
+        let value: f32 = number
+            .trim()
+            .parse()
+            .ok()
+            .filter(|value: &f32| value.is_finite() && *value >= 0.0)
+            .ok_or_else(|| format!("Invalid break time {:?}: expected e.g. 500ms or 1s", time))?;
+        return Ok((value * scale).round() as u32);
+    }
+
+    let strength = strength.unwrap_or("medium");
+    BREAK_STRENGTHS
+        .iter()
+        .find(|(name, _)| *name == strength)
+        .map(|(_, ms)| *ms)
+        .ok_or_else(|| format!("Invalid break strength {:?}", strength))
+}
+
+/// prosody rate：关键字（slow、fast 等）、相对变化（+20%、-10%）、百分比（150%）或倍数（1.5）
+fn parse_rate(value: &str) -> Result<ProsodyRate, String> {
+    let value = value.trim();
+    let multiplier = match RATE_KEYWORDS.iter().find(|(keyword, _)| *keyword == value) {
+        Some((_, multiplier)) => Some(*multiplier),
+        None => match value.strip_suffix('%') {
+            Some(percent) => {
+                let relative = percent.starts_with('+') || percent.starts_with('-');
+                percent
+                    .parse::<f32>()
+                    .ok()
+                    .map(|percent| if relative { 1.0 + percent / 100.0 } else { percent / 100.0 })
+            }
+            None => value.parse::<f32>().ok(),
+        },
+    };
+
+    match multiplier.filter(|multiplier| multiplier.is_finite() && *multiplier > 0.0) {
+        Some(multiplier) => Ok(ProsodyRate {
+            value: value.to_string(),
+            multiplier,
+        }),
+        None => Err(format!("Invalid prosody rate {:?}", value)),
+    }
+}
+
+/// 替换 XML 实体，包括 &#NN; 和 &#xNN; 形式的字符引用
+fn unescape(text: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after.find(';').ok_or_else(|| format!("Unterminated entity in {:?}", text))?;
+        let entity = &after[..end];
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|decimal| decimal.parse::<u32>()))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("Unknown entity &{};", entity))?,
+        };
+        result.push(c);
+        rest = &after[end + 1..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// 加入一段普通文字；emphasize_next 为 true 时将其中第一个单词包在 emphasis 中
+fn push_say_text(text: &str, nodes: &mut Vec<SsmlNode>, pending: &mut String, emphasize_next: &mut bool) {
+    let start = match text.find(|c: char| !c.is_whitespace()) {
+        Some(start) if *emphasize_next => start,
+        _ => {
+            pending.push_str(text);
+            return;
+        }
+    };
+    let end = text[start..].find(char::is_whitespace).map_or(text.len(), |end| start + end);
+    
+    pending.push_str(&text[..start]);
+    flush_text(nodes, pending);
+    nodes.push(SsmlNode::Emphasis(vec![SsmlNode::Text(text[start..end].to_string())]));
+    pending.push_str(&text[end..]);
+    *emphasize_next = false;
+}
+
+fn flush_text(nodes: &mut Vec<SsmlNode>, pending: &mut String) {
+    if !pending.is_empty() {
+        nodes.push(SsmlNode::Text(std::mem::take(pending)));
+    }
+}
+
+#[cfg(any(not(target_os = "macos"), test))]
+fn map_nodes(nodes: Vec<SsmlNode>, f: &dyn Fn(&str) -> String) -> Vec<SsmlNode> {
+    nodes
+        .into_iter()
+        .map(|node| match node {
+            SsmlNode::Text(text) => SsmlNode::Text(f(&text)),
+            SsmlNode::Break(ms) => SsmlNode::Break(ms),
+            SsmlNode::Emphasis(children) => SsmlNode::Emphasis(map_nodes(children, f)),
+            SsmlNode::Prosody { rate, children } => SsmlNode::Prosody {
+                rate,
+                children: map_nodes(children, f),
+            },
+        })
+        .collect()
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn write_nodes(nodes: &[SsmlNode], output: &mut String) {
+    for node in nodes {
+        match node {
+            SsmlNode::Text(text) => output.push_str(&escape(text)),
+            SsmlNode::Break(ms) => output.push_str(&format!(r#"<break time="{}ms"/>"#, ms)),
+            SsmlNode::Emphasis(children) => {
+                output.push_str("<emphasis>");
+                write_nodes(children, output);
+                output.push_str("</emphasis>");
+            }
+            SsmlNode::Prosody { rate, children } => {
+                match rate {
+                    Some(rate) => output.push_str(&format!(r#"<prosody rate="{}">"#, escape(&rate.value))),
+                    None => output.push_str("<prosody>"),
+                }
+                write_nodes(children, output);
+                output.push_str("</prosody>");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> SsmlNode {
+        SsmlNode::Text(text.to_string())
+    }
+
+    #[test]
+    fn say_markup_commands() {
+        let ssml = Ssml::from_say_markup("Wait [[slnc 500]] then [[emph +]] stress this.");
+        assert_eq!(
+            ssml.nodes,
+            vec![
+                text("Wait "),
+                SsmlNode::Break(500),
+                text(" then  "),
+                SsmlNode::Emphasis(vec![text("stress")]),
+                text(" this."),
+            ]
+        );
+
+        // [[emph -]] 忽略，命令内部多余的空白不影响解析
+        let ssml = Ssml::from_say_markup("a [[emph -]]b [[ slnc   20 ]]");
+        assert_eq!(ssml.nodes, vec![text("a b "), SsmlNode::Break(20)]);
+    }
+
+    #[test]
+    fn say_markup_keeps_unknown_commands_literal() {
+        // 不支持的命令、无效的停顿时长和未闭合的 [[ 都按原文保留，不会执行
+        for input in ["[[volm 0.1]] quiet", "[[slnc abc]] x", "[[slnc -5]]", "[[slnc 1 2]]", "open [[slnc 100", "]] [ ["] {
+            assert_eq!(Ssml::from_say_markup(input).nodes, vec![text(input)], "{}", input);
+        }
+        assert!(Ssml::from_say_markup("").nodes.is_empty());
+    }
+
+    #[test]
+    fn say_markup_emphasis_waits_for_next_word() {
+        let ssml = Ssml::from_say_markup("[[emph +]][[slnc 10]]  word");
+        assert_eq!(
+            ssml.nodes,
+            vec![SsmlNode::Break(10), text("  "), SsmlNode::Emphasis(vec![text("word")])]
+        );
+        // 末尾的 [[emph +]] 之后没有单词时不产生空的强调
+        assert_eq!(Ssml::from_say_markup("end [[emph +]]").nodes, vec![text("end ")]);
+    }
+
+    #[test]
+    fn parse_rejects_unsupported_markup() {
+        assert!(Ssml::parse("<speak><audio src=\"x\"/></speak>").is_err());
+        assert!(Ssml::parse("<speak><break time=\"1s\">").is_err());
+        let ssml = Ssml::parse("<speak>a &amp; b<break time=\"250ms\"/></speak>").unwrap();
+        assert_eq!(ssml.nodes, vec![text("a & b"), SsmlNode::Break(250)]);
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    #[test]
+    fn to_document_escapes_text() {
+        let ssml = Ssml::from_say_markup("1 < 2 & \"x\" [[slnc 5]]");
+        assert_eq!(
+            ssml.to_document(Some("en-US")),
+            r#"<speak version="1.0" xmlns="http://www.w3.org/2001/10/synthesis" xml:lang="en-US">1 &lt; 2 &amp; &quot;x&quot; <break time="5ms"/></speak>"#
+        );
+    }
+}
@@ -0,0 +1,617 @@
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::layout;
+use crate::ocr::{is_cjk_char, OcrResult};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Chapter {
+    pub title: String,
+    pub text: String,
+    pub start_page: usize, // 章节起始页在输入结果中的下标
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RevealChunk {
+    pub text: String,
+    pub delay_ms: u64, // 显示该片段后，显示下一个片段前建议等待的时间
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OcrCorrection {
+    pub original: String,
+    pub replacement: String,
+    pub start: usize, // 原词在原文中的字符位置
+    pub rule: String, // 使用的修正规则
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RepairResult {
+    pub repaired: String,
+    pub changes: Vec<OcrCorrection>,
+}
+
+/// 用于 rn/m 混淆修正的英文常用词
+/// 同时收录本身含 rn 的词（modern、turn），避免把正确的词改错
+const ENGLISH_WORDS: &[&str] = &[
+    "am", "come", "comes", "coming", "common", "community", "company", "complete", "form", "former",
+    "from", "government", "human", "image", "important", "information", "make", "makes", "making",
+    "man", "many", "may", "me", "memory", "men", "might", "modern", "moment", "more", "morning",
+    "most", "mother", "much", "must", "my", "name", "number", "problem", "program", "return",
+    "same", "seem", "seems", "small", "some", "something", "sometimes", "system", "them", "then",
+    "time", "times", "turn", "learn", "born", "corner", "concern", "eastern", "western", "pattern",
+    "remember", "summer", "term", "terms", "team", "women", "him", "whom", "home", "become",
+];
+
+/// 常见的序数词后缀，1st、2nd 之类的词不做数字/字母修正
+const ORDINAL_SUFFIXES: &[&str] = &["st", "nd", "rd", "th"];
+
+// 逐字显示时每个节拍的基础间隔，以及句末和换行处额外停顿的时间
+const REVEAL_TICK_MS: u64 = 40;
+const REVEAL_SENTENCE_PAUSE_MS: u64 = 250;
+const REVEAL_LINE_PAUSE_MS: u64 = 150;
+
+/// 常见的独立成章的标题（整行匹配，不区分大小写）
+const STANDALONE_HEADINGS: &[&str] = &[
+    "preface", "prologue", "introduction", "epilogue", "afterword",
+    "序", "序言", "前言", "引言", "楔子", "尾声", "后记",
+];
+
+/// 英文章节标题的前缀关键字
+const HEADING_KEYWORDS: &[&str] = &["chapter", "part", "book"];
+
+/// 判断小写的罗马数字是否合法，相当于 ^m{0,3}(cm|cd|d?c{0,3})(xc|xl|l?x{0,3})(ix|iv|v?i{0,3})$
+fn is_roman_numeral(number: &str) -> bool {
+    if number.is_empty() {
+        return false;
+    }
+
+    let mut rest = number;
+    for _ in 0..3 {
+        match rest.strip_prefix('m') {
+            Some(stripped) => rest = stripped,
+            None => break,
+        }
+    }
+    // 依次为百位、十位、个位：(一, 五, 十) 对应的字母
+    for (one, five, ten) in [('c', 'd', 'm'), ('x', 'l', 'c'), ('i', 'v', 'x')] {
+        let mut chars = rest.chars();
+        let (first, second) = (chars.next(), chars.next());
+        if first == Some(one) && (second == Some(ten) || second == Some(five)) {
+            rest = &rest[2..];
+            continue;
+        }
+        if let Some(stripped) = rest.strip_prefix(five) {
+            rest = stripped;
+        }
+        for _ in 0..3 {
+            match rest.strip_prefix(one) {
+                Some(stripped) => rest = stripped,
+                None => break,
+            }
+        }
+    }
+    rest.is_empty()
+}
+
+/// 判断一行文字是否为顶层章节标题
+/// 识别 "Chapter 3"、"PART IV"、"第十二章"、"第一部" 以及前言/后记之类的独立标题
+fn is_chapter_heading(line: &str) -> bool {
+    let line = line.trim();
+    if line.is_empty() || line.chars().count() > 40 {
+        return false;
+    }
+
+    let lower = line.to_lowercase();
+    if STANDALONE_HEADINGS.contains(&lower.as_str()) {
+        return true;
+    }
+
+    // 英文: 关键字 + 阿拉伯数字或罗马数字
+    // 罗马数字须为大写（或只有一个字母），避免把 "Part mix" 这样的普通单词当作编号
+    let mut words = line.split_whitespace();
+    if let (Some(first), Some(second)) = (words.next(), words.next()) {
+        if HEADING_KEYWORDS.contains(&first.to_lowercase().as_str()) {
+            let number = second.trim_end_matches(['.', ':']);
+            if !number.is_empty()
+                && (number.chars().all(|c| c.is_ascii_digit())
+                    || (is_roman_numeral(&number.to_lowercase())
+                        && (number.chars().count() == 1 || number == number.to_uppercase())))
+            {
+                return true;
+            }
+        }
+    }
+
+    // 中文: 第 + 数字 + 章/部/卷/篇，之后为行尾、空白或分隔符（"第三章 风起"、"第三章：风起"），
+    // 避免把 "第三章的内容……" 这样的正文当作标题
+    if let Some(rest) = line.strip_prefix('第') {
+        let mut chars = rest
+            .chars()
+            .skip_while(|c| c.is_ascii_digit() || "零〇一二三四五六七八九十百千两".contains(*c));
+        let number_len = rest.chars().count() - chars.clone().count();
+        if number_len > 0 {
+            if let Some(unit) = chars.next() {
+                return "章部卷篇".contains(unit)
+                    && chars.next().map_or(true, |c| c.is_whitespace() || "：:、．.·—-".contains(c));
+            }
+        }
+    }
+
+    false
+}
+
+/// 按顶层章节标题将多页识别结果切分为章节
+/// 第一个标题之前的内容单独成为一个无标题章节；没有检测到任何标题时整个文档作为一个章节返回
+fn chapters_from_pages(pages: &[String]) -> Vec<Chapter> {
+    let mut chapters: Vec<Chapter> = Vec::new();
+    let mut current: Option<Chapter> = None;
+
+    for (page_index, page_text) in pages.iter().enumerate() {
+        for line in page_text.lines() {
+            if is_chapter_heading(line) {
+                if let Some(chapter) = current.take() {
+                    // 丢弃标题前仅含空白的无标题章节
+                    if !chapter.title.is_empty() || !chapter.text.trim().is_empty() {
+                        chapters.push(chapter);
+                    }
+                }
+                current = Some(Chapter {
+                    title: line.trim().to_string(),
+                    text: String::new(),
+                    start_page: page_index,
+                });
+                continue;
+            }
+
+            let chapter = current.get_or_insert_with(|| Chapter {
+                title: String::new(),
+                text: String::new(),
+                start_page: page_index,
+            });
+            chapter.text.push_str(line);
+            chapter.text.push('\n');
+        }
+    }
+
+    if let Some(chapter) = current {
+        chapters.push(chapter);
+    }
+
+    // 整个文档都没有文字时仍然返回一个空章节
+    if chapters.is_empty() && !pages.is_empty() {
+        chapters.push(Chapter {
+            title: String::new(),
+            text: String::new(),
+            start_page: 0,
+        });
+    }
+
+    for chapter in &mut chapters {
+        chapter.text = chapter.text.trim().to_string();
+    }
+
+    chapters
+}
+
+#[command]
+pub async fn split_into_chapters(results: Vec<OcrResult>) -> Vec<Chapter> {
+    let pages: Vec<String> = results.into_iter().map(|result| result.text).collect();
+    chapters_from_pages(&pages)
+}
+
+/// 将文本拆分为不可再分的显示单元
+/// 中日韩文字每个字单独成为一个单元，其他文字按单词划分；空白附在前一个单元的末尾
+fn reveal_units(text: &str) -> Vec<String> {
+    let mut units: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for c in text.chars() {
+        if c.is_whitespace() {
+            current.push(c);
+            continue;
+        }
+
+        let current_ends_with_space = current.ends_with(char::is_whitespace);
+        let current_is_word = current.chars().next().is_some_and(|first| !is_cjk_char(first));
+        // 单词内部的字符继续累积，其余情况开始一个新单元
+        if !current.is_empty()
+            && (is_cjk_char(c) || current_ends_with_space || !current_is_word)
+        {
+            units.push(std::mem::take(&mut current));
+        }
+        current.push(c);
+    }
+
+    if !current.is_empty() {
+        units.push(current);
+    }
+
+    units
+}
+
+/// 根据片段末尾的字符给出额外停顿
+fn reveal_pause(chunk: &str) -> u64 {
+    if chunk.ends_with('\n') {
+        return REVEAL_LINE_PAUSE_MS;
+    }
+    match chunk.trim_end().chars().last() {
+        Some('.' | '!' | '?' | '。' | '！' | '？' | '…') => REVEAL_SENTENCE_PAUSE_MS,
+        _ => 0,
+    }
+}
+
+/// 将文本切分为逐步显示的片段，每个片段约 chars_per_tick 个字符
+/// 片段只在单词或中日韩文字的边界处切分，超长的单词会单独成为一个片段
+fn reveal_chunks(text: &str, chars_per_tick: usize) -> Vec<RevealChunk> {
+    let chars_per_tick = chars_per_tick.max(1);
+    let mut chunks: Vec<RevealChunk> = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0;
+
+    let mut finish_chunk = |chunk: String, len: usize| {
+        let ticks = len.div_ceil(chars_per_tick).max(1) as u64;
+        let delay_ms = ticks * REVEAL_TICK_MS + reveal_pause(&chunk);
+        chunks.push(RevealChunk { text: chunk, delay_ms });
+    };
+
+    for unit in reveal_units(text) {
+        // 只计算可见字符，空白不占节拍
+        let unit_len = unit.chars().filter(|c| !c.is_whitespace()).count();
+        if current_len > 0 && current_len + unit_len > chars_per_tick {
+            finish_chunk(std::mem::take(&mut current), current_len);
+            current_len = 0;
+        }
+        current.push_str(&unit);
+        current_len += unit_len;
+        // 换行处总是结束当前片段，便于在行尾停顿
+        if unit.ends_with('\n') {
+            finish_chunk(std::mem::take(&mut current), current_len);
+            current_len = 0;
+        }
+    }
+
+    if !current.is_empty() {
+        finish_chunk(current, current_len);
+    }
+
+    chunks
+}
+
+#[command]
+pub async fn segment_for_reveal(text: String, chars_per_tick: usize) -> Vec<RevealChunk> {
+    reveal_chunks(&text, chars_per_tick)
+}
+
+/// 修正单个词中的常见OCR混淆，不需要修正时返回 None
+/// 规则都比较保守：只在上下文足以判断时修正，宁可漏改也不误改
+fn repair_token(token: &str, english: bool) -> Option<(String, &'static str)> {
+    let chars: Vec<char> = token.chars().collect();
+    if chars.len() < 2 {
+        return None;
+    }
+
+    let digits = chars.iter().filter(|c| c.is_ascii_digit()).count();
+    let letters = chars.iter().filter(|c| c.is_ascii_alphabetic()).count();
+
+    // 数字中混入了形似的字母: 2O23 -> 2023, l00 -> 100
+    let letter_digits = chars.iter().filter(|c| matches!(c, 'O' | 'o' | 'l' | 'I')).count();
+    if digits > 0 && letter_digits > 0 && digits + letter_digits == chars.len() && digits >= letter_digits {
+        let repaired = chars
+            .iter()
+            .map(|&c| match c {
+                'O' | 'o' => '0',
+                'l' | 'I' => '1',
+                c => c,
+            })
+            .collect();
+        return Some((repaired, "letter_in_number"));
+    }
+
+    // 单词中混入了形似的数字: w0rld -> world
+    // 序数词（1st、10th）和带单位的数字（10px）不修正，且字母需明显多于被替换的数字
+    let digit_letters = chars.iter().filter(|c| matches!(c, '0' | '1')).count();
+    let is_ordinal = ORDINAL_SUFFIXES.iter().any(|suffix| {
+        token.to_lowercase().strip_suffix(suffix).is_some_and(|rest| {
+            !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit())
+        })
+    });
+    if digit_letters > 0
+        && digits == digit_letters
+        && letters + digits == chars.len()
+        && letters > digit_letters * 2
+        && !is_ordinal
+    {
+        let uppercase = chars.iter().filter(|c| c.is_ascii_alphabetic()).all(|c| c.is_ascii_uppercase());
+        let repaired = chars
+            .iter()
+            .map(|&c| match (c, uppercase) {
+                ('0', true) => 'O',
+                ('0', false) => 'o',
+                ('1', true) => 'I',
+                ('1', false) => 'l',
+                (c, _) => c,
+            })
+            .collect();
+        return Some((repaired, "digit_in_word"));
+    }
+
+    // rn 被识别成 m 的反向情况: rnodern -> modern，仅在修正后是已知英文单词时修正
+    if english && digits == 0 {
+        let lower = token.to_lowercase();
+        if !ENGLISH_WORDS.contains(&lower.as_str()) {
+            // 逐个尝试替换每一处 rn，rnodern 中只有第一处是误识别
+            for (index, _) in lower.match_indices("rn") {
+                let candidate = format!("{}m{}", &lower[..index], &lower[index + 2..]);
+                if ENGLISH_WORDS.contains(&candidate.as_str()) {
+                    let m = if token[index..].starts_with('R') { "M" } else { "m" };
+                    let repaired = format!("{}{}{}", &token[..index], m, &token[index + 2..]);
+                    return Some((repaired, "rn_as_m"));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// 按词扫描文本并修正常见OCR混淆，返回修正后的文本和每一处修改
+fn repair_confusions(text: &str, language: Option<&str>) -> RepairResult {
+    let english = language.map_or(true, |language| language.to_lowercase().starts_with("en"));
+    let mut repaired = String::with_capacity(text.len());
+    let mut changes = Vec::new();
+    let mut token = String::new();
+    let mut token_start = 0;
+
+    let mut flush = |token: &mut String, token_start: usize, repaired: &mut String| {
+        if token.is_empty() {
+            return;
+        }
+        match repair_token(token, english) {
+            Some((replacement, rule)) => {
+                repaired.push_str(&replacement);
+                changes.push(OcrCorrection {
+                    original: std::mem::take(token),
+                    replacement,
+                    start: token_start,
+                    rule: rule.to_string(),
+                });
+            }
+            None => repaired.push_str(&std::mem::take(token)),
+        }
+    };
+
+    for (index, c) in text.chars().enumerate() {
+        // 只处理由拉丁字母和数字组成的词
+        if c.is_ascii_alphanumeric() {
+            if token.is_empty() {
+                token_start = index;
+            }
+            token.push(c);
+        } else {
+            flush(&mut token, token_start, &mut repaired);
+            repaired.push(c);
+        }
+    }
+    flush(&mut token, token_start, &mut repaired);
+
+    RepairResult { repaired, changes }
+}
+
+#[command]
+pub async fn repair_ocr_confusions(text: String, language: Option<String>) -> RepairResult {
+    repair_confusions(&text, language.as_deref())
+}
+
+/// 去掉行尾用于断词的连字符，把被折行拆开的单词接回去："exam-\nple" -> "example"
+/// 是否为断词的规则与合并段落相同，见 layout::is_split_word
+pub(crate) fn dehyphenate(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut lines = text.split('\n').peekable();
+
+    while let Some(line) = lines.next() {
+        match lines.peek() {
+            // 去掉连字符和换行符，下一行直接接在后面
+            Some(next_line) if layout::is_split_word(line, next_line) => result.push_str(&line[..line.len() - 1]),
+            Some(_) => {
+                result.push_str(line);
+                result.push('\n');
+            }
+            None => result.push_str(line),
+        }
+    }
+
+    result
+}
+
+/// 将连续多个空行（只含空白的行）合并为一个空行
+pub(crate) fn collapse_blank_lines(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.split('\n') {
+        let blank = line.trim().is_empty();
+        if blank && lines.last().is_some_and(|last| last.trim().is_empty()) {
+            continue;
+        }
+        lines.push(if blank { "" } else { line });
+    }
+    lines.join("\n")
+}
+
+/// 将全角ASCII字符（全角字母、数字和标点，U+FF01..U+FF5E）和全角空格转换为对应的半角字符
+/// 。、「」等中日韩标点没有对应的半角ASCII字符，保持不变
+pub(crate) fn normalize_fullwidth(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{3000}' => ' ',
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(pages: &[&str]) -> Vec<String> {
+        pages.iter().map(|page| page.to_string()).collect()
+    }
+
+    #[test]
+    fn roman_numerals() {
+        for number in ["i", "iv", "ix", "xiv", "xl", "xcix", "mcmxcix", "mmxxiv", "cd"] {
+            assert!(is_roman_numeral(number), "{}", number);
+        }
+        for number in ["", "mid", "civil", "did", "iiii", "vx", "il", "mmmm", "dd"] {
+            assert!(!is_roman_numeral(number), "{}", number);
+        }
+    }
+
+    #[test]
+    fn chapter_headings() {
+        for line in ["Chapter 3", "CHAPTER 12.", "Part IV", "Book XII:", "Part I", "chapter v", "Prologue", "第十二章", "第3卷", "第一章 风起", "第二部：归来", "后记"] {
+            assert!(is_chapter_heading(line), "{}", line);
+        }
+        for line in ["Part mid", "Book civil", "Book did", "Part mix", "Chapter one", "第一次", "第三章的内容", "Chapter", "The chapter 3 ends here and the story keeps going"] {
+            assert!(!is_chapter_heading(line), "{}", line);
+        }
+    }
+
+    #[test]
+    fn chapters_across_pages() {
+        let chapters = chapters_from_pages(&pages(&[
+            "Title page\nby Someone",
+            "Chapter 1\nIt was a dark night.\nThe end of page two.",
+            "Still chapter one.\nCHAPTER II\nA new day.",
+            "第三章\n第三章的正文。",
+        ]));
+
+        let summary: Vec<(&str, &str, usize)> = chapters
+            .iter()
+            .map(|chapter| (chapter.title.as_str(), chapter.text.as_str(), chapter.start_page))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("", "Title page\nby Someone", 0),
+                ("Chapter 1", "It was a dark night.\nThe end of page two.\nStill chapter one.", 1),
+                ("CHAPTER II", "A new day.", 2),
+                ("第三章", "第三章的正文。", 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn chapters_without_headings() {
+        let chapters = chapters_from_pages(&pages(&["one", "two"]));
+        assert_eq!(chapters.len(), 1);
+        assert_eq!(chapters[0].title, "");
+        assert_eq!(chapters[0].text, "one\ntwo");
+
+        // 标题前只有空白时不产生无标题章节
+        let chapters = chapters_from_pages(&pages(&["\n  \nPreface\nhello"]));
+        assert_eq!(chapters.len(), 1);
+        assert_eq!(chapters[0].title, "Preface");
+
+        let chapters = chapters_from_pages(&pages(&["", ""]));
+        assert_eq!(chapters.len(), 1);
+        assert!(chapters[0].text.is_empty());
+        assert!(chapters_from_pages(&[]).is_empty());
+    }
+
+    #[test]
+    fn reveal_units_split_words_and_cjk() {
+        assert_eq!(reveal_units("Hello, world  中文"), vec!["Hello, ", "world  ", "中", "文"]);
+        assert_eq!(reveal_units("中文abc。"), vec!["中", "文", "abc", "。"]);
+        assert!(reveal_units("").is_empty());
+    }
+
+    #[test]
+    fn reveal_chunks_respect_boundaries_and_pauses() {
+        let chunks = reveal_chunks("one two three. four\nfive", 8);
+        let texts: Vec<&str> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(texts, vec!["one two ", "three. ", "four\n", "five"]);
+
+        let delays: Vec<u64> = chunks.iter().map(|chunk| chunk.delay_ms).collect();
+        assert_eq!(
+            delays,
+            vec![
+                REVEAL_TICK_MS,
+                REVEAL_TICK_MS + REVEAL_SENTENCE_PAUSE_MS,
+                REVEAL_TICK_MS + REVEAL_LINE_PAUSE_MS,
+                REVEAL_TICK_MS,
+            ]
+        );
+
+        // 超长的单词单独成为一个片段，按字符数计算节拍
+        let chunks = reveal_chunks("a extraordinarily b", 4);
+        assert_eq!(chunks[1].text, "extraordinarily ");
+        assert_eq!(chunks[1].delay_ms, 4 * REVEAL_TICK_MS);
+
+        // chars_per_tick 为 0 时按 1 处理
+        let chunks = reveal_chunks("中文", 0);
+        assert_eq!(chunks.len(), 2);
+    }
+
+    #[test]
+    fn repair_tokens() {
+        let repair = |token: &str| repair_token(token, true);
+        assert_eq!(repair("2O23"), Some(("2023".to_string(), "letter_in_number")));
+        assert_eq!(repair("l00"), Some(("100".to_string(), "letter_in_number")));
+        assert_eq!(repair("w0rld"), Some(("world".to_string(), "digit_in_word")));
+        assert_eq!(repair("HE1LO"), Some(("HEILO".to_string(), "digit_in_word")));
+        assert_eq!(repair("rnodern"), Some(("modern".to_string(), "rn_as_m")));
+        assert_eq!(repair("Rnore"), Some(("More".to_string(), "rn_as_m")));
+
+        // 不应修正的词
+        for token in ["1st", "10th", "10px", "modern", "turn", "a1", "2023", "hello", "x"] {
+            assert_eq!(repair(token), None, "{}", token);
+        }
+        // 非英文时不做 rn/m 修正
+        assert_eq!(repair_token("rnodern", false), None);
+    }
+
+    #[test]
+    fn repair_confusions_reports_changes() {
+        let result = repair_confusions("In 2O23 the w0rld, 中文 rnore.", Some("en-US"));
+        assert_eq!(result.repaired, "In 2023 the world, 中文 more.");
+        let changes: Vec<(&str, &str, usize)> = result
+            .changes
+            .iter()
+            .map(|change| (change.original.as_str(), change.replacement.as_str(), change.start))
+            .collect();
+        assert_eq!(changes, vec![("2O23", "2023", 3), ("w0rld", "world", 12), ("rnore", "more", 22)]);
+
+        let result = repair_confusions("rnore", Some("fr"));
+        assert_eq!(result.repaired, "rnore");
+        assert!(result.changes.is_empty());
+    }
+
+    #[test]
+    fn dehyphenate_joins_split_words() {
+        assert_eq!(dehyphenate("exam-\nple text"), "example text");
+        assert_eq!(dehyphenate("inter-\nnation-\nal"), "international");
+        assert_eq!(dehyphenate("änder-\nung"), "änderung");
+        // 下一行以大写字母开头、列表项、数字范围和行中的连字符都保持不变
+        for text in ["well-\nKnown", "items:\n- one\n- two", "pages 10-\n12", "a well-known fact", "trailing-", "-\nstart"] {
+            assert_eq!(dehyphenate(text), text);
+        }
+    }
+
+    #[test]
+    fn collapse_blank_line_runs() {
+        assert_eq!(collapse_blank_lines("a\n\n\n\nb"), "a\n\nb");
+        assert_eq!(collapse_blank_lines("a\n \n\t\n\nb\n\nc"), "a\n\nb\n\nc");
+        assert_eq!(collapse_blank_lines("a\nb"), "a\nb");
+        assert_eq!(collapse_blank_lines("\n\n\na"), "\na");
+        assert_eq!(collapse_blank_lines(""), "");
+    }
+
+    #[test]
+    fn normalize_fullwidth_to_halfwidth() {
+        assert_eq!(normalize_fullwidth("ＡＢＣ　ｘｙｚ１２３"), "ABC xyz123");
+        assert_eq!(normalize_fullwidth("（注）：！？～"), "(注):!?~");
+        // 没有对应半角字符的中日韩标点保持不变
+        assert_eq!(normalize_fullwidth("中文。、「」…"), "中文。、「」…");
+    }
+}
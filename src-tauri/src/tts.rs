@@ -1,18 +1,69 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use tauri::{command, Emitter};
+use tauri_plugin_store::StoreExt;
 
+use crate::ssml::Ssml;
 #[cfg(target_os = "macos")]
+use crate::ssml::SsmlNode;
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
 use std::process::Command;
-#[cfg(target_os = "macos")]
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
 use std::sync::Mutex;
-#[cfg(target_os = "macos")]
-use std::collections::HashMap as StdHashMap;
+
+#[cfg(target_os = "windows")]
+use windows::{
+    core::HSTRING,
+    Foundation::TypedEventHandler,
+    Media::{
+        Core::MediaSource,
+        Playback::{MediaPlayer, MediaPlayerFailedEventArgs},
+        SpeechSynthesis::SpeechSynthesizer,
+    },
+};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TtsResult {
     pub success: bool,
     pub process_id: Option<String>,
+    pub output_path: Option<String>, // synthesize_to_file 写入的音频文件路径
     pub error_message: Option<String>,
+    // 失败原因的分类，成功时为 None；error_message 仍给出具体信息
+    #[serde(default)]
+    pub error_code: Option<TtsError>,
+}
+
+/// 朗读失败原因的分类，与 OcrError 一样序列化为稳定的字符串（例如 "voice_not_found"）
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TtsError {
+    // 请求参数有误，例如不支持的音频格式、输出目录不存在
+    InvalidRequest,
+    // 找不到指定的音色
+    VoiceNotFound,
+    // 找不到语音合成程序（macOS 的 say）
+    BinaryNotFound,
+    // 没有该 process_id 对应的朗读
+    NotSpeaking,
+    // 读写设置（发音词典）失败
+    SettingsFailure,
+    // 语音合成或播放失败
+    EngineFailure,
+    // 当前平台不支持该功能
+    UnsupportedPlatform,
+}
+
+impl TtsResult {
+    pub fn failure(code: TtsError, message: String) -> Self {
+        TtsResult {
+            success: false,
+            process_id: None,
+            output_path: None,
+            error_message: Some(message),
+            error_code: Some(code),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -35,292 +86,2290 @@ pub struct VoiceInfo {
     pub identifier: String,
 }
 
-// 在macOS上存储正在运行的TTS进程
+/// 一种语言及其所有音色
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LanguageVoices {
+    pub language: String,
+    pub voices: Vec<VoiceInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AllVoicesResult {
+    pub languages: Vec<LanguageVoices>, // 按语言代码排序
+    pub success: bool,
+    pub error_message: Option<String>,
+}
+
+/// 朗读选项
+/// macOS 上音量和音高通过在文本前插入 say 的 [[volm]]、[[pbas]] 命令实现；
+/// 文本本身含有的 [[...]] 会被转义，按原文朗读而不会被 say 当作命令执行
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SpeakOptions {
+    pub voice: Option<String>,
+    pub rate_wpm: Option<u32>, // 语速（每分钟单词数），超出 50~500 时取边界值
+    pub volume: Option<f32>,   // 音量 0~1，超出时取边界值
+    pub pitch: Option<f32>,    // 基准音高（Hz），取值 30~300，超出时取边界值；各平台的换算见 MIN_PITCH_HZ
+}
+
+/// tts-error 事件的内容：朗读异常结束（say、spd-say 或 espeak-ng 返回非零退出码，或Windows播放失败）
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TtsErrorEvent {
+    pub process_id: String,
+    pub error_message: String,
+}
+
+/// tts-word-boundary 事件的内容：即将朗读的文字在传入文本中的字符范围
+#[cfg(target_os = "macos")]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WordBoundaryEvent {
+    pub process_id: String,
+    pub char_start: usize,
+    pub char_end: usize,
+    pub granularity: String, // "word" 或 "sentence"，文本很长时按句子发送
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VoiceTiming {
+    pub voice: String,
+    pub ms: u64, // 合成示例文本所用的毫秒数
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FastestVoiceResult {
+    pub voice: String,
+    pub ms: u64,
+    pub timings: Vec<VoiceTiming>, // 所有参与测试的音色及其耗时
+    pub success: bool,
+    pub error_message: Option<String>,
+}
+
+// 基准音高的允许范围（Hz）
+// macOS 换算为 say 的 [[pbas]]（MIDI 音符编号），Windows 换算为 SetAudioPitch 的 0~2（相对于 NORMAL_PITCH_HZ 的倍数），
+// Linux 按相对于 NORMAL_PITCH_HZ 的八度数换算为 spd-say 的 -p 或 espeak-ng 的 -p
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+const MIN_PITCH_HZ: f32 = 30.0;
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+const MAX_PITCH_HZ: f32 = 300.0;
+// Windows 和 Linux 默认音高对应的基准音高（Hz）
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+const NORMAL_PITCH_HZ: f32 = 120.0;
+
+/// 基准音高换算为 say 的 [[pbas]]：MIDI 音符编号，A4（440 Hz）为 69
+#[cfg(target_os = "macos")]
+fn pitch_to_pbas(pitch_hz: f32) -> f32 {
+    69.0 + 12.0 * (pitch_hz.clamp(MIN_PITCH_HZ, MAX_PITCH_HZ) / 440.0).log2()
+}
+
+/// 基准音高相对于 NORMAL_PITCH_HZ 的八度数，高于正常音高时为正
+#[cfg(target_os = "linux")]
+fn pitch_octaves(pitch_hz: f32) -> f32 {
+    (pitch_hz.clamp(MIN_PITCH_HZ, MAX_PITCH_HZ) / NORMAL_PITCH_HZ).log2()
+}
+
+// 语速的允许范围（每分钟单词数），各平台和所有朗读命令共用；下限 50 供需要慢速朗读的语言学习者使用
+// Windows 的 SpeakingRate 最低为 0.5 倍，低于约 90 时按 0.5 倍朗读
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+const MIN_RATE_WPM: u32 = 50;
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+const MAX_RATE_WPM: u32 = 500;
+
+// Windows 语音合成的正常语速（每分钟单词数），SpeakingRate 为相对于它的倍数，取值 0.5~6
+#[cfg(target_os = "windows")]
+const WINDOWS_NORMAL_RATE_WPM: f64 = 180.0;
+#[cfg(target_os = "windows")]
+const WINDOWS_MIN_SPEAKING_RATE: f64 = 0.5;
+
+// spd-say 的正常语速（每分钟单词数），-r 为相对于它的 -100~100
+#[cfg(target_os = "linux")]
+const SPD_SAY_NORMAL_RATE_WPM: f32 = 180.0;
+
+#[cfg(target_os = "linux")]
+const LINUX_TTS_NOT_FOUND: &str = "Neither spd-say nor espeak-ng was found on PATH. Install speech-dispatcher (e.g. `sudo apt install speech-dispatcher`) or espeak-ng to use TTS on Linux";
+
+// synthesize_to_file 支持的音频格式：(格式名, 文件扩展名, say 的格式参数)
+#[cfg(target_os = "macos")]
+const AUDIO_FORMATS: &[(&str, &str, &[&str])] = &[
+    ("aiff", "aiff", &["--file-format=AIFF"]),
+    ("m4a", "m4a", &["--file-format=m4af", "--data-format=aac"]),
+    // 32位浮点、22050Hz 的 WAV，便于缓存后再处理
+    ("wav", "wav", &["--file-format=WAVE", "--data-format=LEF32@22050"]),
+];
+
+// say 的默认语速（每分钟单词数），用于估计单词边界的时间
+#[cfg(target_os = "macos")]
+const DEFAULT_SAY_RATE_WPM: u32 = 175;
+// 单词数超过该值时改为按句子发送边界事件，避免事件过多
+#[cfg(target_os = "macos")]
+const MAX_WORD_BOUNDARY_EVENTS: usize = 1000;
+
+// 测速时最多测试的音色数量
+#[cfg(target_os = "macos")]
+const MAX_BENCHMARK_VOICES: usize = 6;
+
+// 前端设置所在的store文件，以及发音词典在其中的键
+const SETTINGS_STORE: &str = ".settings.dat";
+const PRONUNCIATIONS_KEY: &str = "tts_pronunciations";
+
+// 在macOS和Linux上存储正在运行的TTS进程
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+lazy_static::lazy_static! {
+    static ref TTS_PROCESSES: Mutex<HashMap<String, std::process::Child>> = Mutex::new(HashMap::new());
+}
+
 #[cfg(target_os = "macos")]
 lazy_static::lazy_static! {
-    static ref TTS_PROCESSES: Mutex<StdHashMap<String, std::process::Child>> = Mutex::new(StdHashMap::new());
+    // 按语言缓存的测速结果
+    static ref FASTEST_VOICES: Mutex<HashMap<String, FastestVoiceResult>> = Mutex::new(HashMap::new());
+    // 已被 pause_speaking 暂停的朗读
+    static ref PAUSED_PROCESSES: Mutex<std::collections::HashSet<String>> = Mutex::new(std::collections::HashSet::new());
+}
+
+// 在Windows上存储正在播放的朗读
+#[cfg(target_os = "windows")]
+lazy_static::lazy_static! {
+    static ref TTS_PLAYERS: Mutex<HashMap<String, MediaPlayer>> = Mutex::new(HashMap::new());
+}
+
+/// 要朗读的内容：纯文本，或已解析的 SSML
+enum SpeechInput {
+    Text(String),
+    Ssml(Ssml),
+}
+
+/// 朗读队列中的一条
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+struct QueuedSpeech {
+    app_handle: tauri::AppHandle,
+    input: SpeechInput,
+    options: SpeakOptions,
+    process_id: String,
+    started: Option<std::sync::mpsc::Sender<TtsResult>>, // 调用方等待开始朗读的结果时，工作线程通过它返回结果
+}
+
+/// 朗读队列的状态
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+#[derive(Default)]
+struct SpeechQueueState {
+    pending: std::collections::VecDeque<QueuedSpeech>,
+    // 正在朗读的条目的 process_id；清空队列或插队时被取走，工作线程据此得知本条已被停止
+    current: Option<String>,
+    worker_started: bool, // 第一次入队时启动工作线程
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+lazy_static::lazy_static! {
+    static ref SPEECH_QUEUE: Mutex<SpeechQueueState> = Mutex::new(SpeechQueueState::default());
+    // 有新条目入队时通知工作线程
+    static ref SPEECH_QUEUE_READY: std::sync::Condvar = std::sync::Condvar::new();
 }
 
+/// 朗读文本，rate 为语速（每分钟单词数，50~500，超出时取边界值），未指定时使用系统默认语速
+/// pitch 和 volume 的取值范围见 SpeakOptions
+/// text 和 ssml 必须且只能提供一个；ssml 支持的元素见 ssml.rs，格式错误时返回 invalid_request 而不会朗读
+/// macOS 上 SSML 转换为 say 的嵌入命令，并且不发送 tts-word-boundary 事件
+/// 默认 text 中的 [[...]] 按原文朗读；markup 为 true 时支持其中的 [[slnc N]]（停顿 N 毫秒）和 [[emph +]]（强调下一个单词），
+/// 各平台效果一致，其余 [[...]] 仍按原文朗读，转换规则见 Ssml::from_say_markup
+///
+/// 经过朗读队列，避免连续调用时多段朗读重叠：
+/// - 队列空闲时立即开始，返回开始朗读的结果
+/// - 正在朗读时排到队尾，立即返回本条的 process_id，之后启动失败时通过 tts-error 和 tts-finished 通知
+/// - interrupt 为 true 时停止正在朗读的条目并插到队首，队列中其余条目在本条之后继续朗读
+///
+/// 朗读过程中向前端发送的事件：
+/// - tts-queue-advanced：队列开始处理下一条，内容为该条的 process_id 字符串
+/// - tts-started：开始播放，内容为 process_id 字符串
+/// - tts-word-boundary：即将朗读的单词（仅macOS，为估计值），内容见 WordBoundaryEvent
+/// - tts-error：朗读异常结束，内容见 TtsErrorEvent，之后仍会发送 tts-finished
+/// - tts-finished：朗读结束（包括异常结束，但不包括被 stop_speaking 停止），内容为 process_id 字符串
+/// - tts-paused / tts-resumed：被 pause_speaking / resume_speaking 暂停或继续，内容为 process_id 字符串
+// 参数与前端调用时的字段一一对应，不合并为结构体
+#[allow(clippy::too_many_arguments)]
 #[command]
-pub async fn speak_text(app_handle: tauri::AppHandle, text: String, voice: Option<String>) -> TtsResult {
+pub async fn speak_text(
+    app_handle: tauri::AppHandle,
+    text: Option<String>,
+    voice: Option<String>,
+    rate: Option<u32>,
+    pitch: Option<f32>,
+    volume: Option<f32>,
+    ssml: Option<String>,
+    interrupt: Option<bool>,
+    markup: Option<bool>,
+) -> TtsResult {
+    let input = match (text, ssml) {
+        (Some(text), None) if markup.unwrap_or(false) => SpeechInput::Ssml(Ssml::from_say_markup(&text)),
+        (Some(text), None) => SpeechInput::Text(text),
+        (None, Some(ssml)) => match Ssml::parse(&ssml) {
+            Ok(ssml) => SpeechInput::Ssml(ssml),
+            Err(e) => return TtsResult::failure(TtsError::InvalidRequest, format!("Invalid SSML: {}", e)),
+        },
+        _ => {
+            return TtsResult::failure(
+                TtsError::InvalidRequest,
+                "Exactly one of text or ssml must be provided".to_string(),
+            );
+        }
+    };
+    
+    let options = SpeakOptions {
+        voice,
+        rate_wpm: rate,
+        volume,
+        pitch,
+    };
+    queue_speech(app_handle, input, options, interrupt.unwrap_or(false), true).await
+}
+
+/// 按指定的音色、语速、音量和音高立即朗读文本，不经过朗读队列
+#[command]
+pub async fn speak_text_with_options(app_handle: tauri::AppHandle, text: String, options: SpeakOptions) -> TtsResult {
+    start_speaking(app_handle, SpeechInput::Text(text), options, uuid::Uuid::new_v4().to_string()).await
+}
+
+/// 以指定的 process_id 开始朗读，立即朗读和朗读队列共用
+async fn start_speaking(app_handle: tauri::AppHandle, input: SpeechInput, options: SpeakOptions, process_id: String) -> TtsResult {
+    // 单词边界按替换前的原文计算，字符位置与前端传入的文本一致；SSML 不发送单词边界
+    #[cfg(target_os = "macos")]
+    let units = match &input {
+        SpeechInput::Text(text) => speech_units(text),
+        SpeechInput::Ssml(_) => vec![],
+    };
+    
+    // 朗读前先按发音词典替换专有名词，SSML 只替换其中的文字
+    // macOS 上在转换为 say 的文本时替换，只转义原文，读法中的 [[inpt PHON]] 等嵌入命令保留
+    let pronunciations = load_pronunciations(&app_handle);
+    #[cfg(not(target_os = "macos"))]
+    let input = match input {
+        SpeechInput::Text(text) => SpeechInput::Text(apply_pronunciations(&text, &pronunciations)),
+        SpeechInput::Ssml(ssml) => SpeechInput::Ssml(ssml.map_text(&|text| apply_pronunciations(text, &pronunciations))),
+    };
+
     #[cfg(target_os = "macos")]
     {
-        speak_text_macos(app_handle, text, voice).await
+        let text = say_input_text(&input, &pronunciations, options.rate_wpm.unwrap_or(DEFAULT_SAY_RATE_WPM));
+        speak_text_macos(app_handle, text, options, units, process_id).await
     }
     
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(target_os = "windows")]
     {
-        TtsResult {
-            success: false,
+        speak_text_windows(app_handle, input, options, process_id).await
+    }
+    
+    #[cfg(target_os = "linux")]
+    {
+        speak_text_linux(app_handle, input, options, process_id).await
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        let _ = (input, options, process_id);
+        TtsResult::failure(TtsError::UnsupportedPlatform, "TTS is only available on macOS, Windows and Linux".to_string())
+    }
+}
+
+/// 生成交给 say 朗读的文本：按发音词典替换，并转义原文中的 [[ ]]；SSML 转换为 say 的嵌入命令
+#[cfg(target_os = "macos")]
+fn say_input_text(input: &SpeechInput, pronunciations: &HashMap<String, String>, rate_wpm: u32) -> String {
+    match input {
+        SpeechInput::Text(text) => say_text(text, pronunciations),
+        SpeechInput::Ssml(ssml) => {
+            let mut markup = String::new();
+            ssml_to_say_markup(&ssml.nodes, pronunciations, rate_wpm, false, &mut markup);
+            markup
+        }
+    }
+}
+
+/// 保存发音词典：键为原词，值为替换后的读法（可以是拼读，也可以是 say 的 [[inpt PHON]] 标记）
+#[command]
+pub async fn set_pronunciations(app_handle: tauri::AppHandle, map: HashMap<String, String>) -> TtsResult {
+    let store = match app_handle.store(SETTINGS_STORE) {
+        Ok(store) => store,
+        Err(e) => {
+            return TtsResult::failure(TtsError::SettingsFailure, format!("Failed to open settings store: {}", e));
+        }
+    };
+
+    store.set(PRONUNCIATIONS_KEY, serde_json::json!(map));
+
+    match store.save() {
+        Ok(_) => TtsResult {
+            success: true,
             process_id: None,
-            error_message: Some("TTS is only available on macOS".to_string()),
+            output_path: None,
+            error_message: None,
+            error_code: None,
+        },
+        Err(e) => TtsResult::failure(TtsError::SettingsFailure, format!("Failed to save pronunciations: {}", e)),
+    }
+}
+
+/// 从store中读取发音词典，读取失败时视为空词典
+fn load_pronunciations(app_handle: &tauri::AppHandle) -> HashMap<String, String> {
+    app_handle
+        .store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(PRONUNCIATIONS_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// 判断字符是否属于一个"单词"（字母或数字，不含中日韩文字）
+/// 中日韩文字之间没有词边界，因此词典中的中文词条可以在句中任意位置匹配
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() && (c as u32) < 0x2E80
+}
+
+fn lowercase_char(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// 按发音词典切分得到的文本片段
+enum PronunciationSegment<'a> {
+    Text(String),        // 原文中没有匹配到词条的部分
+    Respelling(&'a str), // 词条的读法，可能含有 say 的嵌入命令
+}
+
+/// 按发音词典替换文本中的词语
+/// 只在 Windows 和 Linux 上直接使用；macOS 上由 say_text 替换，以便只转义原文
+#[cfg(any(not(target_os = "macos"), test))]
+fn apply_pronunciations(text: &str, pronunciations: &HashMap<String, String>) -> String {
+    pronunciation_segments(text, pronunciations)
+        .into_iter()
+        .map(|segment| match segment {
+            PronunciationSegment::Text(text) => text,
+            PronunciationSegment::Respelling(respelling) => respelling.to_string(),
+        })
+        .collect()
+}
+
+/// 按发音词典替换文字，并转义原文中的 [[ ]]；词条的读法原样保留，其中的 [[inpt PHON]] 等嵌入命令照常生效
+#[cfg(target_os = "macos")]
+fn say_text(text: &str, pronunciations: &HashMap<String, String>) -> String {
+    pronunciation_segments(text, pronunciations)
+        .into_iter()
+        .map(|segment| match segment {
+            PronunciationSegment::Text(text) => escape_say_commands(&text),
+            PronunciationSegment::Respelling(respelling) => respelling.to_string(),
+        })
+        .collect()
+}
+
+/// 在文本中查找发音词典的词条，按顺序返回未匹配的原文和匹配到的词条读法
+/// 整词匹配、不区分大小写；多个词条重叠时优先匹配最长的词条
+fn pronunciation_segments<'a>(text: &str, pronunciations: &'a HashMap<String, String>) -> Vec<PronunciationSegment<'a>> {
+    if pronunciations.is_empty() {
+        return vec![PronunciationSegment::Text(text.to_string())];
+    }
+
+    // 按词条长度从长到短排列，保证最长匹配优先
+    let mut entries: Vec<(Vec<char>, &String)> = pronunciations
+        .iter()
+        .filter(|(word, _)| !word.trim().is_empty())
+        .map(|(word, respelling)| (word.chars().map(lowercase_char).collect(), respelling))
+        .collect();
+    entries.sort_by_key(|(word, _)| std::cmp::Reverse(word.len()));
+
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|&c| lowercase_char(c)).collect();
+    let mut segments = Vec::new();
+    let mut plain = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let matched = entries.iter().find(|(word, _)| {
+            let end = i + word.len();
+            if end > lower.len() || lower[i..end] != word[..] {
+                return false;
+            }
+            // 词条首尾是字母数字时，要求匹配位置两侧也是词边界
+            let starts_on_boundary = i == 0 || !is_word_char(word[0]) || !is_word_char(lower[i - 1]);
+            let ends_on_boundary = end == lower.len()
+                || !is_word_char(word[word.len() - 1])
+                || !is_word_char(lower[end]);
+            starts_on_boundary && ends_on_boundary
+        });
+
+        match matched {
+            Some((word, respelling)) => {
+                if !plain.is_empty() {
+                    segments.push(PronunciationSegment::Text(std::mem::take(&mut plain)));
+                }
+                segments.push(PronunciationSegment::Respelling(respelling));
+                i += word.len();
+            }
+            None => {
+                plain.push(chars[i]);
+                i += 1;
+            }
         }
     }
+    if !plain.is_empty() {
+        segments.push(PronunciationSegment::Text(plain));
+    }
+
+    segments
 }
 
 #[command]
 pub async fn stop_speaking(process_id: String) -> TtsResult {
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     {
-        stop_speaking_macos(process_id).await
+        stop_speaking_process(process_id).await
     }
     
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(target_os = "windows")]
     {
-        TtsResult {
-            success: false,
-            process_id: None,
-            error_message: Some("TTS is only available on macOS".to_string()),
-        }
+        stop_speaking_windows(process_id).await
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        TtsResult::failure(TtsError::UnsupportedPlatform, "TTS is only available on macOS, Windows and Linux".to_string())
     }
 }
 
+/// 暂停朗读，之后可以用 resume_speaking 从暂停处继续
+/// 成功时发送 tts-paused 事件，内容为 process_id 字符串；process_id 未知或朗读已结束时返回失败
 #[command]
-pub async fn get_supported_tts_languages() -> LanguageResult {
+pub async fn pause_speaking(app_handle: tauri::AppHandle, process_id: String) -> TtsResult {
+    set_speech_paused(&app_handle, &process_id, true)
+}
+
+/// 继续被 pause_speaking 暂停的朗读，成功时发送 tts-resumed 事件，内容为 process_id 字符串
+#[command]
+pub async fn resume_speaking(app_handle: tauri::AppHandle, process_id: String) -> TtsResult {
+    set_speech_paused(&app_handle, &process_id, false)
+}
+
+fn set_speech_paused(app_handle: &tauri::AppHandle, process_id: &str, paused: bool) -> TtsResult {
     #[cfg(target_os = "macos")]
-    {
-        get_supported_languages_macos().await
-    }
+    let result = set_speech_paused_macos(process_id, paused);
     
-    #[cfg(not(target_os = "macos"))]
-    {
-        LanguageResult {
-            languages: vec![],
-            success: false,
-            error_message: Some("TTS is only available on macOS".to_string()),
+    #[cfg(target_os = "windows")]
+    let result = set_speech_paused_windows(process_id, paused);
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let result: std::result::Result<(), (TtsError, String)> = Err((
+        TtsError::UnsupportedPlatform,
+        "Pausing speech is only available on macOS and Windows".to_string(),
+    ));
+    
+    match result {
+        Ok(()) => {
+            let event = if paused { "tts-paused" } else { "tts-resumed" };
+            let _ = app_handle.emit(event, process_id.to_string());
+            TtsResult {
+                success: true,
+                process_id: None,
+                output_path: None,
+                error_message: None,
+                error_code: None,
+            }
         }
+        Err((code, message)) => TtsResult::failure(code, message),
     }
 }
 
+/// 将文本合成为音频文件而不是直接播放
+/// format 为 aiff（默认）、m4a 或 wav；output_path 的扩展名与格式不符时会替换为对应的扩展名，实际写入的路径见返回值的 output_path
 #[command]
-pub async fn get_voices_for_language(language: String) -> VoiceResult {
+pub async fn synthesize_to_file(
+    app_handle: tauri::AppHandle,
+    text: String,
+    voice: Option<String>,
+    output_path: String,
+    format: Option<String>,
+) -> TtsResult {
+    // 与朗读一致，按发音词典替换专有名词
+    let pronunciations = load_pronunciations(&app_handle);
+
     #[cfg(target_os = "macos")]
     {
-        get_voices_for_language_macos(language).await
+        // 合成整段文本可能需要较长时间，放到阻塞线程池中执行
+        let result = tauri::async_runtime::spawn_blocking(move || {
+            synthesize_to_file_macos(text, pronunciations, voice, output_path, format)
+        })
+        .await;
+        match result {
+            Ok(result) => result,
+            Err(e) => TtsResult::failure(TtsError::EngineFailure, format!("Failed to run speech synthesis: {}", e)),
+        }
     }
     
     #[cfg(not(target_os = "macos"))]
     {
-        VoiceResult {
-            voices: vec![],
-            success: false,
-            error_message: Some("TTS is only available on macOS".to_string()),
-        }
+        let _ = (text, pronunciations, voice, output_path, format);
+        TtsResult::failure(TtsError::UnsupportedPlatform, "Synthesizing to a file is only available on macOS".to_string())
     }
 }
 
-#[cfg(target_os = "macos")]
-async fn speak_text_macos(app_handle: tauri::AppHandle, text: String, voice: Option<String>) -> TtsResult {
-    use std::process::{Command, Stdio};
-    use uuid::Uuid;
-    
-    // 生成唯一的进程ID
-    let process_id = Uuid::new_v4().to_string();
-    
-    // 构建say命令
-    let mut cmd = Command::new("say");
-    
-    // 如果指定了音色，则添加-v参数
-    if let Some(voice_name) = voice {
-        cmd.arg("-v").arg(voice_name);
+/// 停止本应用启动的所有朗读，不影响其他应用的 say 进程
+/// Linux 上使用 spd-say 时，speech-dispatcher 中其他应用的朗读也会被取消
+/// 朗读队列中尚未开始的条目也一并丢弃
+#[command]
+pub async fn stop_all_speaking() -> TtsResult {
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    {
+        let mut queue = SPEECH_QUEUE.lock().unwrap();
+        queue.pending.clear();
+        queue.current = None;
     }
     
-    // 添加要朗读的文本
-    cmd.arg(&text);
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        stop_all_speaking_processes().await
+    }
     
-    // 重定向输出以避免阻塞
-    cmd.stdout(Stdio::null());
-    cmd.stderr(Stdio::null());
+    #[cfg(target_os = "windows")]
+    {
+        stop_all_speaking_windows().await
+    }
     
-    match cmd.spawn() {
-        Ok(mut child) => {
-            // 克隆app_handle用于在线程中发送事件
-            let app_handle_clone = app_handle.clone();
-            let process_id_clone = process_id.clone();
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        TtsResult::failure(TtsError::UnsupportedPlatform, "TTS is only available on macOS, Windows and Linux".to_string())
+    }
+}
+
+/// 将文本加入朗读队列，队列中的文本按加入顺序逐条朗读，上一条结束后才开始下一条
+/// 返回的 process_id 是该条的ID，朗读时的事件（与 speak_text 相同，每条结束时发送 tts-finished）和 stop_speaking 都使用这个ID
+///
+/// 与其他命令的关系：
+/// - speak_text 同样经过队列，区别在于队列空闲时等待开始朗读并返回结果，并且可以插队
+/// - speak_text_with_options 立即开始朗读，不经过队列，会与队列中正在朗读的条目重叠
+/// - stop_speaking 停止队列中正在朗读的条目后，队列继续朗读下一条
+/// - clear_speech_queue 和 stop_all_speaking 丢弃尚未开始的条目并停止当前条目，被丢弃的条目不发送任何事件
+#[command]
+pub async fn enqueue_speech(app_handle: tauri::AppHandle, text: String, voice: Option<String>, rate: Option<u32>) -> TtsResult {
+    let options = SpeakOptions {
+        voice,
+        rate_wpm: rate,
+        ..Default::default()
+    };
+    queue_speech(app_handle, SpeechInput::Text(text), options, false, false).await
+}
+
+/// 将一条朗读加入队列；interrupt 为 true 时插到队首并停止正在朗读的条目
+/// wait_if_idle 为 true 且本条会立即开始（队列空闲或插队）时，等待开始朗读并返回其结果，否则立即返回本条的 process_id
+async fn queue_speech(
+    app_handle: tauri::AppHandle,
+    input: SpeechInput,
+    options: SpeakOptions,
+    interrupt: bool,
+    wait_if_idle: bool,
+) -> TtsResult {
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    {
+        let process_id = uuid::Uuid::new_v4().to_string();
+        let (receiver, interrupted) = {
+            let mut queue = SPEECH_QUEUE.lock().unwrap();
+            if !queue.worker_started {
+                std::thread::spawn(speech_queue_worker);
+                queue.worker_started = true;
+            }
             
-            // 在单独的线程中等待进程完成
-            std::thread::spawn(move || {
-                // 等待进程完成
-                let _ = child.wait();
-                
-                // 发送朗读完成事件到前端
-                let _ = app_handle_clone.emit("tts-finished", process_id_clone);
-            });
+            let idle = queue.current.is_none() && queue.pending.is_empty();
+            let (sender, receiver) = if wait_if_idle && (idle || interrupt) {
+                let (sender, receiver) = std::sync::mpsc::channel();
+                (Some(sender), Some(receiver))
+            } else {
+                (None, None)
+            };
+            let item = QueuedSpeech {
+                app_handle,
+                input,
+                options,
+                process_id: process_id.clone(),
+                started: sender,
+            };
             
-            TtsResult {
+            let interrupted = if interrupt {
+                queue.pending.push_front(item);
+                queue.current.take()
+            } else {
+                queue.pending.push_back(item);
+                None
+            };
+            (receiver, interrupted)
+        };
+        SPEECH_QUEUE_READY.notify_one();
+        
+        if let Some(interrupted) = interrupted {
+            let _ = stop_speaking(interrupted).await;
+        }
+        
+        match receiver {
+            Some(receiver) => match tauri::async_runtime::spawn_blocking(move || receiver.recv()).await {
+                Ok(Ok(result)) => result,
+                // 开始之前队列被清空，条目连同 sender 一起被丢弃
+                _ => TtsResult::failure(
+                    TtsError::NotSpeaking,
+                    "Speech was removed from the queue before it started".to_string(),
+                ),
+            },
+            None => TtsResult {
                 success: true,
                 process_id: Some(process_id),
+                output_path: None,
                 error_message: None,
-            }
-        }
-        Err(e) => {
-            TtsResult {
-                success: false,
-                process_id: None,
-                error_message: Some(format!("Failed to start TTS: {}", e)),
-            }
+                error_code: None,
+            },
         }
     }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        let _ = (app_handle, input, options, interrupt, wait_if_idle);
+        TtsResult::failure(TtsError::UnsupportedPlatform, "TTS is only available on macOS, Windows and Linux".to_string())
+    }
 }
 
-#[cfg(target_os = "macos")]
-async fn stop_speaking_macos(process_id: String) -> TtsResult {
-    let mut processes = TTS_PROCESSES.lock().unwrap();
-    
-    if let Some(mut child) = processes.remove(&process_id) {
-        // 终止进程
-        let _ = child.kill();
-        let _ = child.wait();
+/// 清空朗读队列：丢弃尚未开始的条目，并停止正在朗读的条目
+#[command]
+pub async fn clear_speech_queue() -> TtsResult {
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    {
+        let current = {
+            let mut queue = SPEECH_QUEUE.lock().unwrap();
+            queue.pending.clear();
+            queue.current.take()
+        };
+        if let Some(process_id) = current {
+            let _ = stop_speaking(process_id).await;
+        }
         
         TtsResult {
             success: true,
             process_id: None,
+            output_path: None,
             error_message: None,
-        }
-    } else {
-        // 如果找不到进程，尝试使用macOS的afplay命令停止所有音频
-        let output = Command::new("killall")
-            .arg("say")
-            .output();
-            
-        match output {
-            Ok(_) => TtsResult {
-                success: true,
-                process_id: None,
-                error_message: None,
-            },
-            Err(e) => TtsResult {
-                success: false,
-                process_id: None,
-                error_message: Some(format!("Failed to stop TTS: {}", e)),
-            },
+            error_code: None,
         }
     }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        TtsResult::failure(TtsError::UnsupportedPlatform, "TTS is only available on macOS, Windows and Linux".to_string())
+    }
 }
 
-#[cfg(target_os = "macos")]
-async fn get_supported_languages_macos() -> LanguageResult {
-    // 使用say -v '?'命令获取支持的语言和音色
-    let output = Command::new("say")
-        .arg("-v")
-        .arg("?")
-        .output();
-        
-    match output {
-        Ok(output) => {
-            if output.status.success() {
-                let output_str = String::from_utf8_lossy(&output.stdout);
-                let mut languages = Vec::new();
-                let mut language_set = std::collections::HashSet::new();
-                
-                // 解析say -v '?'的输出来提取语言
-                for line in output_str.lines() {
-                    // 正确解析格式: 语音名称    语言代码    # 语音示例
-                    // 从右向左查找语言代码，使用#作为参考点
-                    if let Some(hash_pos) = line.rfind("#") {
-                        // 获取#之前的部分
-                        let before_hash = &line[..hash_pos].trim();
-                        // 获取语言代码（#之前部分的最后一个字段）
-                        if let Some(last_space_pos) = before_hash.rfind(|c: char| c.is_whitespace()) {
-                            let lang_code = before_hash[last_space_pos..].trim();
-                            // 转换语言代码格式 (en_US -> en-US)
-                            let normalized_lang = lang_code.replace("_", "-");
-                            language_set.insert(normalized_lang);
-                        }
-                    }
-                }
-                
-                languages.extend(language_set);
-                languages.sort();
-                
-                LanguageResult {
-                    languages,
-                    success: true,
-                    error_message: None,
-                }
-            } else {
-                let error = String::from_utf8_lossy(&output.stderr);
-                LanguageResult {
-                    languages: vec![],
-                    success: false,
-                    error_message: Some(format!("Failed to get supported languages: {}", error)),
+/// 朗读队列的工作线程：逐条朗读，等待上一条结束（或被停止）后再开始下一条
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+fn speech_queue_worker() {
+    use tauri::async_runtime::block_on;
+    
+    loop {
+        let item = {
+            let mut queue = SPEECH_QUEUE.lock().unwrap();
+            let item = loop {
+                match queue.pending.pop_front() {
+                    Some(item) => break item,
+                    None => queue = SPEECH_QUEUE_READY.wait(queue).unwrap(),
                 }
+            };
+            queue.current = Some(item.process_id.clone());
+            item
+        };
+        
+        let app_handle = item.app_handle.clone();
+        let process_id = item.process_id.clone();
+        let _ = app_handle.emit("tts-queue-advanced", process_id.clone());
+        
+        let result = block_on(start_speaking(item.app_handle, item.input, item.options, item.process_id));
+        let success = result.success;
+        let error_message = result.error_message.clone().unwrap_or_default();
+        // 调用方在等待结果时，启动失败直接返回给调用方，不再发送事件
+        let reported = item.started.is_some_and(|sender| sender.send(result).is_ok());
+        
+        if success {
+            // 开始朗读期间队列被清空或被插队时，current 已被取走，需要停止本条
+            if SPEECH_QUEUE.lock().unwrap().current.as_deref() != Some(process_id.as_str()) {
+                let _ = block_on(stop_speaking(process_id.clone()));
             }
+            while is_speaking(&process_id) {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+        } else if !reported {
+            // 入队时已经返回成功，启动失败只能通过事件通知前端
+            let _ = app_handle.emit("tts-error", TtsErrorEvent {
+                process_id: process_id.clone(),
+                error_message,
+            });
+            let _ = app_handle.emit("tts-finished", process_id.clone());
         }
-        Err(e) => {
-            LanguageResult {
+        
+        let mut queue = SPEECH_QUEUE.lock().unwrap();
+        if queue.current.as_deref() == Some(process_id.as_str()) {
+            queue.current = None;
+        }
+    }
+}
+
+/// 列出正在朗读（包括已暂停）的 process_id，按字典序排列，可配合 stop_speaking 停止其中任意一条
+/// 朗读结束后等待线程（Windows 上为播放结束事件）会先将其移出表，再发送 tts-finished，因此列表中不含已结束的朗读
+/// 朗读队列中尚未开始的条目不在列表中
+#[command]
+pub async fn list_active_tts() -> Vec<String> {
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    let mut process_ids: Vec<String> = TTS_PROCESSES.lock().unwrap().keys().cloned().collect();
+    
+    #[cfg(target_os = "windows")]
+    let mut process_ids: Vec<String> = TTS_PLAYERS.lock().unwrap().keys().cloned().collect();
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    let mut process_ids: Vec<String> = vec![];
+    
+    process_ids.sort();
+    process_ids
+}
+
+/// 朗读是否仍在进行：朗读结束或被停止后会从表中移除
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+fn is_speaking(process_id: &str) -> bool {
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        TTS_PROCESSES.lock().unwrap().contains_key(process_id)
+    }
+    
+    #[cfg(target_os = "windows")]
+    {
+        TTS_PLAYERS.lock().unwrap().contains_key(process_id)
+    }
+}
+
+#[command]
+pub async fn get_supported_tts_languages() -> LanguageResult {
+    #[cfg(target_os = "macos")]
+    {
+        get_supported_languages_macos().await
+    }
+    
+    #[cfg(target_os = "windows")]
+    {
+        get_supported_languages_windows().await
+    }
+    
+    #[cfg(target_os = "linux")]
+    {
+        get_supported_languages_linux().await
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        LanguageResult {
+            languages: vec![],
+            success: false,
+            error_message: Some("TTS is only available on macOS, Windows and Linux".to_string()),
+        }
+    }
+}
+
+#[command]
+pub async fn get_voices_for_language(language: String) -> VoiceResult {
+    #[cfg(target_os = "macos")]
+    {
+        get_voices_for_language_macos(language).await
+    }
+    
+    #[cfg(target_os = "windows")]
+    {
+        get_voices_for_language_windows(language).await
+    }
+    
+    #[cfg(target_os = "linux")]
+    {
+        get_voices_for_language_linux(language).await
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        VoiceResult {
+            voices: vec![],
+            success: false,
+            error_message: Some("TTS is only available on macOS, Windows and Linux".to_string()),
+        }
+    }
+}
+
+/// 一次列出所有音色并按语言分组，用于构建完整的音色选择列表，避免逐个语言调用 get_voices_for_language
+#[command]
+pub async fn get_all_voices() -> AllVoicesResult {
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    {
+        #[cfg(target_os = "macos")]
+        let voices = macos_voices();
+        #[cfg(target_os = "windows")]
+        let voices = windows_voices();
+        #[cfg(target_os = "linux")]
+        let voices = linux_voices();
+        
+        match voices {
+            Ok(voices) => AllVoicesResult {
+                languages: group_voices_by_language(voices),
+                success: true,
+                error_message: None,
+            },
+            Err(e) => AllVoicesResult {
                 languages: vec![],
                 success: false,
-                error_message: Some(format!("Failed to execute say command: {}", e)),
+                error_message: Some(e),
+            },
+        }
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        AllVoicesResult {
+            languages: vec![],
+            success: false,
+            error_message: Some("TTS is only available on macOS, Windows and Linux".to_string()),
+        }
+    }
+}
+
+/// 将 (音色, 语言) 按语言分组，语言按代码排序，同一语言内保持原有顺序
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+fn group_voices_by_language(voices: Vec<(VoiceInfo, String)>) -> Vec<LanguageVoices> {
+    let mut groups: std::collections::BTreeMap<String, Vec<VoiceInfo>> = std::collections::BTreeMap::new();
+    for (voice, language) in voices {
+        groups.entry(language).or_default().push(voice);
+    }
+    groups
+        .into_iter()
+        .map(|(language, voices)| LanguageVoices { language, voices })
+        .collect()
+}
+
+/// 检查朗读后端能否使用，可用时返回后端名称和已安装的音色数量
+/// 与 get_voices_for_language 一样列出音色：macOS 上运行 say，Linux 上优先使用 spd-say，Windows 上读取 WinRT 的音色列表
+pub fn check_tts_backend() -> Result<(&'static str, usize), String> {
+    #[cfg(target_os = "macos")]
+    {
+        macos_voices().map(|voices| ("say", voices.len()))
+    }
+    
+    #[cfg(target_os = "windows")]
+    {
+        windows_voices().map(|voices| ("Windows.Media.SpeechSynthesis", voices.len()))
+    }
+    
+    #[cfg(target_os = "linux")]
+    {
+        // linux_voices 在找不到 spd-say 时才使用 espeak-ng
+        let voices = linux_voices()?;
+        let spd_say_found = Command::new("spd-say").arg("--version").output().is_ok();
+        Ok((if spd_say_found { "spd-say" } else { "espeak-ng" }, voices.len()))
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        Err("TTS is only available on macOS, Windows and Linux".to_string())
+    }
+}
+
+/// 对指定语言的音色逐个测速，返回合成示例文本最快的音色
+/// 结果按语言缓存，refresh 为 true 时重新测速
+#[command]
+pub async fn select_fastest_voice(language: String, sample_text: String, refresh: Option<bool>) -> FastestVoiceResult {
+    #[cfg(target_os = "macos")]
+    {
+        select_fastest_voice_macos(language, sample_text, refresh.unwrap_or(false)).await
+    }
+    
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = (language, sample_text, refresh);
+        FastestVoiceResult {
+            voice: String::new(),
+            ms: 0,
+            timings: vec![],
+            success: false,
+            error_message: Some("Voice benchmarking is only available on macOS".to_string()),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+async fn speak_text_macos(
+    app_handle: tauri::AppHandle,
+    text: String,
+    options: SpeakOptions,
+    units: Vec<SpeechUnit>,
+    process_id: String,
+) -> TtsResult {
+    use std::process::{Command, Stdio};
+    
+    // 构建say命令
+    let mut cmd = Command::new("say");
+    
+    // 如果指定了音色，则添加-v参数
+    if let Some(voice_name) = options.voice {
+        cmd.arg("-v").arg(voice_name);
+    }
+    
+    // 语速超出范围时取边界值，而不是报错
+    let rate_wpm = options.rate_wpm.map(|rate_wpm| rate_wpm.clamp(MIN_RATE_WPM, MAX_RATE_WPM));
+    if let Some(rate_wpm) = rate_wpm {
+        cmd.arg("-r").arg(rate_wpm.to_string());
+    }
+    
+    // say 没有音量和音高参数，用嵌入文本的 [[volm]]、[[pbas]] 命令设置
+    let text = match options.volume {
+        Some(volume) => format!("[[volm {}]] {}", volume.clamp(0.0, 1.0), text),
+        None => text,
+    };
+    let text = match options.pitch {
+        Some(pitch) => format!("[[pbas {:.1}]] {}", pitch_to_pbas(pitch), text),
+        None => text,
+    };
+    
+    // 添加要朗读的文本
+    cmd.arg(&text);
+    
+    // 重定向输出以避免阻塞
+    cmd.stdout(Stdio::null());
+    cmd.stderr(Stdio::null());
+    
+    match cmd.spawn() {
+        Ok(child) => {
+            // 记录子进程，stop_speaking 根据 process_id 只终止对应的朗读
+            TTS_PROCESSES.lock().unwrap().insert(process_id.clone(), child);
+            
+            // 克隆app_handle用于在线程中发送事件
+            let app_handle_clone = app_handle.clone();
+            let process_id_clone = process_id.clone();
+            
+            // say 不提供朗读进度，按语速估计每个单词的时间并发送边界事件
+            let app_handle_boundaries = app_handle.clone();
+            let process_id_boundaries = process_id.clone();
+            let rate_wpm = rate_wpm.unwrap_or(DEFAULT_SAY_RATE_WPM);
+            std::thread::spawn(move || {
+                emit_estimated_boundaries(&app_handle_boundaries, &process_id_boundaries, units, rate_wpm);
+            });
+            
+            // 在单独的线程中等待进程完成
+            watch_tts_process(app_handle_clone, process_id_clone, "say");
+            
+            let _ = app_handle.emit("tts-started", process_id.clone());
+            
+            TtsResult {
+                success: true,
+                process_id: Some(process_id),
+                output_path: None,
+                error_message: None,
+                error_code: None,
             }
         }
+        Err(e) => {
+            let code = if e.kind() == std::io::ErrorKind::NotFound {
+                TtsError::BinaryNotFound
+            } else {
+                TtsError::EngineFailure
+            };
+            TtsResult::failure(code, format!("Failed to start TTS: {}", e))
+        }
     }
 }
 
+/// 朗读单位：在原文中的字符范围，以及估计的相对时长（一个英文单词为 1）
 #[cfg(target_os = "macos")]
-async fn get_voices_for_language_macos(language: String) -> VoiceResult {
-    // 使用say -v '?'命令获取指定语言的音色
-    let output = Command::new("say")
-        .arg("-v")
-        .arg("?")
-        .output();
+#[derive(Debug, Clone)]
+struct SpeechUnit {
+    char_start: usize,
+    char_end: usize,
+    weight: f64,
+    sentence_end: bool, // 是否是句子的最后一个单位
+}
+
+/// 将文本切分为朗读单位：拉丁字母、数字组成的单词各为一个单位，中日韩文字每个字为一个单位
+/// 中文的语速约为每个英文单词两个字；句末标点后的停顿计入前一个单位
+#[cfg(target_os = "macos")]
+fn speech_units(text: &str) -> Vec<SpeechUnit> {
+    let mut units: Vec<SpeechUnit> = Vec::new();
+    let mut word_start: Option<usize> = None;
+    let mut count = 0;
+    
+    for (index, c) in text.chars().enumerate() {
+        count = index + 1;
+        if is_word_char(c) {
+            word_start.get_or_insert(index);
+            continue;
+        }
+        if let Some(start) = word_start.take() {
+            units.push(SpeechUnit { char_start: start, char_end: index, weight: 1.0, sentence_end: false });
+        }
+        if c.is_alphanumeric() {
+            units.push(SpeechUnit { char_start: index, char_end: index + 1, weight: 0.5, sentence_end: false });
+        } else if matches!(c, '.' | '!' | '?' | '。' | '！' | '？') {
+            if let Some(last) = units.last_mut() {
+                if !last.sentence_end {
+                    last.sentence_end = true;
+                    last.weight += 0.5;
+                }
+            }
+        }
+    }
+    if let Some(start) = word_start {
+        units.push(SpeechUnit { char_start: start, char_end: count, weight: 1.0, sentence_end: false });
+    }
+    if let Some(last) = units.last_mut() {
+        last.sentence_end = true;
+    }
+    
+    units
+}
+
+/// 将朗读单位合并为句子
+#[cfg(target_os = "macos")]
+fn sentence_units(units: Vec<SpeechUnit>) -> Vec<SpeechUnit> {
+    let mut sentences: Vec<SpeechUnit> = Vec::new();
+    let mut current: Option<SpeechUnit> = None;
+    
+    for unit in units {
+        let sentence = match current.take() {
+            Some(mut sentence) => {
+                sentence.char_end = unit.char_end;
+                sentence.weight += unit.weight;
+                sentence
+            }
+            None => unit.clone(),
+        };
+        if unit.sentence_end {
+            sentences.push(sentence);
+        } else {
+            current = Some(sentence);
+        }
+    }
+    sentences.extend(current);
+    sentences
+}
+
+/// 按估计的时间点依次发送 tts-word-boundary 事件，朗读被停止后不再发送
+#[cfg(target_os = "macos")]
+fn emit_estimated_boundaries(app_handle: &tauri::AppHandle, process_id: &str, units: Vec<SpeechUnit>, rate_wpm: u32) {
+    use std::time::{Duration, Instant};
+    
+    let (units, granularity) = if units.len() > MAX_WORD_BOUNDARY_EVENTS {
+        (sentence_units(units), "sentence")
+    } else {
+        (units, "word")
+    };
+    
+    let ms_per_word = 60_000.0 / rate_wpm.max(1) as f64;
+    let mut started = Instant::now();
+    let mut offset_ms = 0.0;
+    
+    for unit in units {
+        let target = started + Duration::from_millis(offset_ms as u64);
+        if let Some(remaining) = target.checked_duration_since(Instant::now()) {
+            std::thread::sleep(remaining);
+        }
+        // 暂停期间不发送，之后的边界按暂停的时长顺延
+        let paused_at = Instant::now();
+        while PAUSED_PROCESSES.lock().unwrap().contains(process_id) {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        started += paused_at.elapsed();
+        if !TTS_PROCESSES.lock().unwrap().contains_key(process_id) {
+            return;
+        }
         
-    match output {
-        Ok(output) => {
-            if output.status.success() {
-                let output_str = String::from_utf8_lossy(&output.stdout);
-                let mut voices = Vec::new();
-                
-                // 解析say -v '?'的输出来提取指定语言的音色
-                for line in output_str.lines() {
-                    // 正确解析格式: 语音名称    语言代码    # 语音示例
-                    if let Some(hash_pos) = line.rfind("#") {
-                        // 获取#之前的部分
-                        let before_hash = &line[..hash_pos].trim();
-                        // 获取语言代码（#之前部分的最后一个字段）
-                        if let Some(last_space_pos) = before_hash.rfind(|c: char| c.is_whitespace()) {
-                            let lang_part = &before_hash[last_space_pos..].trim();
-                            // 转换语言代码格式 (en_US -> en-US)
-                            let normalized_lang = lang_part.replace("_", "-");
-                            
-                            // 如果语言匹配，则添加到结果中
-                            if normalized_lang == language || normalized_lang.starts_with(&format!("{}-", language.split('-').next().unwrap_or(&language))) {
-                                // 获取语音名称（#之前部分中语言代码之前的所有内容）
-                                let voice_name = before_hash[..last_space_pos].trim().to_string();
-                                if !voice_name.is_empty() {
-                                    // 生成标识符（简化版本）
-                                    let identifier = format!("{}", 
-                                        voice_name);
-                                    voices.push(VoiceInfo {
-                                        name: voice_name,
-                                        identifier,
-                                    });
-                                }
+        let _ = app_handle.emit("tts-word-boundary", WordBoundaryEvent {
+            process_id: process_id.to_string(),
+            char_start: unit.char_start,
+            char_end: unit.char_end,
+            granularity: granularity.to_string(),
+        });
+        offset_ms += unit.weight * ms_per_word;
+    }
+}
+
+/// 拆开文字中连续的 [ 和 ]，使 say 按原文朗读而不是当作嵌入命令执行；
+/// 逐个字符处理，"[[[" 之类的连续括号拆开后也不会再组成 [[
+#[cfg(target_os = "macos")]
+fn escape_say_commands(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut previous = None;
+    for c in text.chars() {
+        if (c == '[' || c == ']') && previous == Some(c) {
+            escaped.push(' ');
+        }
+        escaped.push(c);
+        previous = Some(c);
+    }
+    escaped
+}
+
+/// 将 SSML 转换为 say 的嵌入命令：break 转为 [[slnc]]，emphasis 中的每个词前加 [[emph +]]，
+/// prosody rate 用 [[rate]] 改变语速，结束后恢复外层的语速；文字按发音词典替换，原文中的 [[ ]] 被转义
+#[cfg(target_os = "macos")]
+fn ssml_to_say_markup(
+    nodes: &[SsmlNode],
+    pronunciations: &HashMap<String, String>,
+    rate_wpm: u32,
+    emphasis: bool,
+    output: &mut String,
+) {
+    for node in nodes {
+        match node {
+            // 词条的读法作为一个整体强调，其中的嵌入命令不拆开
+            SsmlNode::Text(text) if emphasis => {
+                for segment in pronunciation_segments(text, pronunciations) {
+                    match segment {
+                        PronunciationSegment::Text(text) => {
+                            for word in text.split_whitespace() {
+                                output.push_str(&format!(" [[emph +]] {}", escape_say_commands(word)));
                             }
                         }
+                        PronunciationSegment::Respelling(respelling) => {
+                            output.push_str(&format!(" [[emph +]] {}", respelling));
+                        }
                     }
                 }
-                
-                VoiceResult {
-                    voices,
-                    success: true,
-                    error_message: None,
+                output.push(' ');
+            }
+            SsmlNode::Text(text) => output.push_str(&say_text(text, pronunciations)),
+            SsmlNode::Break(ms) => output.push_str(&format!(" [[slnc {}]] ", ms)),
+            SsmlNode::Emphasis(children) => ssml_to_say_markup(children, pronunciations, rate_wpm, true, output),
+            SsmlNode::Prosody { rate, children } => {
+                let inner_rate_wpm = rate.as_ref().map_or(rate_wpm, |rate| {
+                    ((rate_wpm as f32 * rate.multiplier).round() as u32).clamp(MIN_RATE_WPM, MAX_RATE_WPM)
+                });
+                output.push_str(&format!(" [[rate {}]] ", inner_rate_wpm));
+                ssml_to_say_markup(children, pronunciations, inner_rate_wpm, emphasis, output);
+                output.push_str(&format!(" [[rate {}]] ", rate_wpm));
+            }
+        }
+    }
+}
+
+/// 在单独的线程中等待朗读进程结束，异常退出时发送 tts-error，之后发送 tts-finished；被 stop_speaking 停止时不发送
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn watch_tts_process(app_handle: tauri::AppHandle, process_id: String, program: &'static str) {
+    std::thread::spawn(move || {
+        let status = match wait_for_tts_process(&process_id) {
+            Some(status) => status,
+            // 被 stop_speaking 停止
+            None => return,
+        };
+        
+        if !status.success() {
+            let _ = app_handle.emit("tts-error", TtsErrorEvent {
+                process_id: process_id.clone(),
+                error_message: format!("{} exited with {}", program, status),
+            });
+        }
+        
+        // 发送朗读完成事件到前端
+        let _ = app_handle.emit("tts-finished", process_id);
+    });
+}
+
+/// 等待 TTS_PROCESSES 中的朗读进程结束，结束后将其移出表
+/// 不能在持有锁的情况下阻塞 wait()，否则 stop_speaking 无法取得子进程，因此这里轮询
+/// 返回进程的退出状态；进程被 stop_speaking 移出表时返回 None
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn wait_for_tts_process(process_id: &str) -> Option<std::process::ExitStatus> {
+    loop {
+        {
+            let mut processes = TTS_PROCESSES.lock().unwrap();
+            let status = match processes.get_mut(process_id) {
+                Some(child) => child.try_wait(),
+                None => return None,
+            };
+            match status {
+                Ok(None) => {}
+                Ok(Some(status)) => {
+                    processes.remove(process_id);
+                    return Some(status);
                 }
+                // 无法获取状态时视为已结束
+                Err(_) => {
+                    processes.remove(process_id);
+                    return None;
+                }
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+}
+
+/// say 没有暂停功能，用 SIGSTOP / SIGCONT 挂起和恢复朗读进程
+#[cfg(target_os = "macos")]
+fn set_speech_paused_macos(process_id: &str, paused: bool) -> std::result::Result<(), (TtsError, String)> {
+    let pid = match TTS_PROCESSES.lock().unwrap().get(process_id) {
+        Some(child) => child.id(),
+        None => return Err((TtsError::NotSpeaking, format!("No active TTS process with id {}", process_id))),
+    };
+    
+    let signal = if paused { "-STOP" } else { "-CONT" };
+    match Command::new("kill").arg(signal).arg(pid.to_string()).status() {
+        Ok(status) if status.success() => {
+            let mut paused_processes = PAUSED_PROCESSES.lock().unwrap();
+            if paused {
+                paused_processes.insert(process_id.to_string());
             } else {
-                let error = String::from_utf8_lossy(&output.stderr);
-                VoiceResult {
-                    voices: vec![],
-                    success: false,
-                    error_message: Some(format!("Failed to get voices for language: {}", error)),
+                paused_processes.remove(process_id);
+            }
+            Ok(())
+        }
+        Ok(status) => Err((TtsError::EngineFailure, format!("kill {} exited with {}", signal, status))),
+        Err(e) => Err((TtsError::EngineFailure, format!("Failed to execute kill: {}", e))),
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+async fn stop_speaking_process(process_id: String) -> TtsResult {
+    let child = TTS_PROCESSES.lock().unwrap().remove(&process_id);
+    
+    if let Some(mut child) = child {
+        // 只终止对应的朗读进程
+        let _ = child.kill();
+        let _ = child.wait();
+        #[cfg(target_os = "macos")]
+        PAUSED_PROCESSES.lock().unwrap().remove(&process_id);
+        #[cfg(target_os = "linux")]
+        cancel_speech_dispatcher();
+        
+        TtsResult {
+            success: true,
+            process_id: None,
+            output_path: None,
+            error_message: None,
+            error_code: None,
+        }
+    } else {
+        TtsResult::failure(TtsError::NotSpeaking, format!("No active TTS process with id {}", process_id))
+    }
+}
+
+/// 构造将文本写入音频文件的 say -o 命令；与朗读一致，替换发音词典并转义原文中的 [[ ]]
+#[cfg(target_os = "macos")]
+fn say_to_file_command(
+    text: &str,
+    pronunciations: &HashMap<String, String>,
+    voice: Option<&str>,
+    path: &std::path::Path,
+    format_args: &[&str],
+) -> Command {
+    let mut cmd = Command::new("say");
+    if let Some(voice_name) = voice {
+        cmd.arg("-v").arg(voice_name);
+    }
+    cmd.arg("-o").arg(path).args(format_args).arg(say_text(text, pronunciations));
+    cmd
+}
+
+/// 调用 say -o 将文本写入音频文件，不登记到 TTS_PROCESSES
+#[cfg(target_os = "macos")]
+fn synthesize_to_file_macos(
+    text: String,
+    pronunciations: HashMap<String, String>,
+    voice: Option<String>,
+    output_path: String,
+    format: Option<String>,
+) -> TtsResult {
+    let format = format.unwrap_or_else(|| "aiff".to_string()).to_lowercase();
+    let (_, extension, format_args) = match AUDIO_FORMATS.iter().find(|(name, _, _)| *name == format) {
+        Some(audio_format) => audio_format,
+        None => {
+            let supported: Vec<&str> = AUDIO_FORMATS.iter().map(|(name, _, _)| *name).collect();
+            return TtsResult::failure(TtsError::InvalidRequest, format!("Unsupported audio format {}: expected one of {}", format, supported.join(", ")));
+        }
+    };
+    
+    // 输出目录必须已存在；扩展名与格式保持一致
+    let mut path = std::path::PathBuf::from(&output_path);
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => std::path::PathBuf::from("."),
+    };
+    if !directory.is_dir() {
+        return TtsResult::failure(TtsError::InvalidRequest, format!("Output directory does not exist: {}", directory.display()));
+    }
+    if path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()) != Some(extension.to_string()) {
+        path.set_extension(extension);
+    }
+    
+    let mut cmd = say_to_file_command(&text, &pronunciations, voice.as_deref(), &path, format_args);
+    match cmd.output() {
+        Ok(output) if output.status.success() => TtsResult {
+            success: true,
+            process_id: None,
+            output_path: Some(path.to_string_lossy().to_string()),
+            error_message: None,
+            error_code: None,
+        },
+        Ok(output) => {
+            let error = String::from_utf8_lossy(&output.stderr);
+            TtsResult::failure(TtsError::EngineFailure, format!("Failed to synthesize speech to file: {}", error))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            TtsResult::failure(TtsError::BinaryNotFound, format!("Failed to execute say command: {}", e))
+        }
+        Err(e) => TtsResult::failure(TtsError::EngineFailure, format!("Failed to execute say command: {}", e)),
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+async fn stop_all_speaking_processes() -> TtsResult {
+    let children: Vec<std::process::Child> = TTS_PROCESSES
+        .lock()
+        .unwrap()
+        .drain()
+        .map(|(_, child)| child)
+        .collect();
+    
+    #[cfg(target_os = "linux")]
+    let stopped_any = !children.is_empty();
+    for mut child in children {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    #[cfg(target_os = "macos")]
+    PAUSED_PROCESSES.lock().unwrap().clear();
+    #[cfg(target_os = "linux")]
+    if stopped_any {
+        cancel_speech_dispatcher();
+    }
+    
+    TtsResult {
+        success: true,
+        process_id: None,
+        output_path: None,
+        error_message: None,
+        error_code: None,
+    }
+}
+
+/// 解析 say -v '?' 输出中的一行："音色名称    语言代码    # 示例句子"，返回音色名称和 en-US 形式的语言代码
+/// 名称中可能含有空格和括号（新版 macOS 为 "Rocko (English (US))" 的形式），示例句子可能是任意语言的文字，
+/// 因此只使用第一个 # 之前的部分：最后一个字段为语言代码，其余为名称
+#[cfg(target_os = "macos")]
+fn parse_voice_line(line: &str) -> Option<(String, String)> {
+    let before_hash = line[..line.find('#')?].trim();
+    let last_space_pos = before_hash.rfind(char::is_whitespace)?;
+    let name = before_hash[..last_space_pos].trim();
+    let language = before_hash[last_space_pos..].trim();
+    
+    // 语言代码形如 en_US、zh_CN、es_419；缺少语言代码的行不会把名称的最后一个单词误认为语言
+    let mut parts = language.split(['_', '-']);
+    let is_language_code = parts
+        .next()
+        .is_some_and(|primary| (2..=3).contains(&primary.len()) && primary.chars().all(|c| c.is_ascii_lowercase()))
+        && parts.all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()));
+    if name.is_empty() || !is_language_code {
+        return None;
+    }
+    Some((name.to_string(), language.replace('_', "-")))
+}
+
+/// 解析 say -v '?' 的完整输出；音色名称同时作为标识符，say -v 接受名称
+#[cfg(target_os = "macos")]
+fn parse_say_voices(output: &str) -> Vec<(VoiceInfo, String)> {
+    output
+        .lines()
+        .filter_map(parse_voice_line)
+        .map(|(name, language)| {
+            (
+                VoiceInfo {
+                    name: name.clone(),
+                    identifier: name,
+                },
+                language,
+            )
+        })
+        .collect()
+}
+
+/// 使用 say -v '?' 列出 macOS 上的音色及其语言
+#[cfg(target_os = "macos")]
+fn macos_voices() -> Result<Vec<(VoiceInfo, String)>, String> {
+    match Command::new("say").arg("-v").arg("?").output() {
+        Ok(output) if output.status.success() => Ok(parse_say_voices(&String::from_utf8_lossy(&output.stdout))),
+        Ok(output) => Err(format!("Failed to list voices: {}", String::from_utf8_lossy(&output.stderr))),
+        Err(e) => Err(format!("Failed to execute say command: {}", e)),
+    }
+}
+
+#[cfg(target_os = "macos")]
+async fn get_supported_languages_macos() -> LanguageResult {
+    match macos_voices() {
+        Ok(voices) => {
+            let mut languages: Vec<String> = voices.into_iter().map(|(_, language)| language).collect();
+            languages.sort();
+            languages.dedup();
+            LanguageResult {
+                languages,
+                success: true,
+                error_message: None,
+            }
+        }
+        Err(e) => LanguageResult {
+            languages: vec![],
+            success: false,
+            error_message: Some(e),
+        },
+    }
+}
+
+#[cfg(target_os = "macos")]
+async fn get_voices_for_language_macos(language: String) -> VoiceResult {
+    // 语言完全相同，或主语言相同（zh 匹配 zh-CN）
+    let primary = format!("{}-", language.split('-').next().unwrap_or(&language));
+    match macos_voices() {
+        Ok(voices) => VoiceResult {
+            voices: voices
+                .into_iter()
+                .filter(|(_, voice_language)| *voice_language == language || voice_language.starts_with(&primary))
+                .map(|(voice, _)| voice)
+                .collect(),
+            success: true,
+            error_message: None,
+        },
+        Err(e) => VoiceResult {
+            voices: vec![],
+            success: false,
+            error_message: Some(e),
+        },
+    }
+}
+
+#[cfg(target_os = "macos")]
+async fn select_fastest_voice_macos(language: String, sample_text: String, refresh: bool) -> FastestVoiceResult {
+    use std::time::Instant;
+    
+    if !refresh {
+        if let Some(cached) = FASTEST_VOICES.lock().unwrap().get(&language) {
+            return cached.clone();
+        }
+    }
+    
+    let failure = |message: String| FastestVoiceResult {
+        voice: String::new(),
+        ms: 0,
+        timings: vec![],
+        success: false,
+        error_message: Some(message),
+    };
+    
+    let voices = get_voices_for_language_macos(language.clone()).await;
+    if !voices.success {
+        return failure(voices.error_message.unwrap_or_default());
+    }
+    
+    // 逐个合成到临时文件而不是直接播放，避免声音重叠；限制音色数量以控制总耗时
+    // 每次合成都要等待 say 进程结束，放到阻塞线程池中执行；示例文本中的 [[ ]] 同样转义
+    let sample_text = escape_say_commands(&sample_text);
+    let benchmark = tauri::async_runtime::spawn_blocking(move || {
+        let mut timings = Vec::new();
+        for voice in voices.voices.into_iter().take(MAX_BENCHMARK_VOICES) {
+            let mut output_path = std::env::temp_dir();
+            output_path.push(format!("tts_benchmark_{}.aiff", uuid::Uuid::new_v4()));
+            
+            let started = Instant::now();
+            let status = Command::new("say")
+                .arg("-v")
+                .arg(&voice.name)
+                .arg("-o")
+                .arg(&output_path)
+                .arg(&sample_text)
+                .status();
+            let elapsed = started.elapsed();
+            let _ = std::fs::remove_file(&output_path);
+            
+            // 合成失败的音色不参与比较
+            if matches!(status, Ok(status) if status.success()) {
+                timings.push(VoiceTiming {
+                    voice: voice.name,
+                    ms: elapsed.as_millis() as u64,
+                });
+            }
+        }
+        timings
+    })
+    .await;
+    let timings = match benchmark {
+        Ok(timings) => timings,
+        Err(e) => return failure(format!("Failed to benchmark voices: {}", e)),
+    };
+    
+    let fastest = match timings.iter().min_by_key(|timing| timing.ms) {
+        Some(fastest) => fastest.clone(),
+        None => return failure(format!("No voice available for language {}", language)),
+    };
+    
+    let result = FastestVoiceResult {
+        voice: fastest.voice,
+        ms: fastest.ms,
+        timings,
+        success: true,
+        error_message: None,
+    };
+    FASTEST_VOICES.lock().unwrap().insert(language, result.clone());
+    result
+}
+
+/// 判断语音的语言是否属于请求的语言：完全相同，或主语言相同（zh 匹配 zh-CN）
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn voice_matches_language(voice_language: &str, language: &str) -> bool {
+    let primary = language.split('-').next().unwrap_or(language);
+    voice_language.eq_ignore_ascii_case(language)
+        || voice_language
+            .split('-')
+            .next()
+            .is_some_and(|voice_primary| voice_primary.eq_ignore_ascii_case(primary))
+}
+
+#[cfg(target_os = "windows")]
+async fn speak_text_windows(app_handle: tauri::AppHandle, input: SpeechInput, options: SpeakOptions, process_id: String) -> TtsResult {
+    let engine_failure = |message: String| (TtsError::EngineFailure, message);
+    // 直接等待 WinRT 的异步操作，不阻塞异步运行时的工作线程
+    let player = async {
+        let synthesizer = SpeechSynthesizer::new()
+            .map_err(|e| engine_failure(format!("Failed to create speech synthesizer: {:?}", e)))?;
+
+        // 按名称或标识符选择音色
+        if let Some(voice_name) = &options.voice {
+            let voices = SpeechSynthesizer::AllVoices()
+                .map_err(|e| engine_failure(format!("Failed to get voices: {:?}", e)))?;
+            let voice = voices.into_iter().find(|voice| {
+                voice.DisplayName().map(|name| name == *voice_name).unwrap_or(false)
+                    || voice.Id().map(|id| id == *voice_name).unwrap_or(false)
+            });
+            match voice {
+                Some(voice) => synthesizer
+                    .SetVoice(&voice)
+                    .map_err(|e| engine_failure(format!("Failed to set voice {}: {:?}", voice_name, e)))?,
+                None => return Err((TtsError::VoiceNotFound, format!("Voice not found: {}", voice_name))),
+            }
+        }
+
+        let synthesizer_options = synthesizer
+            .Options()
+            .map_err(|e| engine_failure(format!("Failed to get speech synthesizer options: {:?}", e)))?;
+        if let Some(rate_wpm) = options.rate_wpm {
+            let rate = (rate_wpm.clamp(MIN_RATE_WPM, MAX_RATE_WPM) as f64 / WINDOWS_NORMAL_RATE_WPM).max(WINDOWS_MIN_SPEAKING_RATE);
+            synthesizer_options
+                .SetSpeakingRate(rate)
+                .map_err(|e| engine_failure(format!("Failed to set speaking rate: {:?}", e)))?;
+        }
+        if let Some(volume) = options.volume {
+            synthesizer_options
+                .SetAudioVolume(volume.clamp(0.0, 1.0) as f64)
+                .map_err(|e| engine_failure(format!("Failed to set volume: {:?}", e)))?;
+        }
+        // AudioPitch 为 0~2，1 为音色的默认音高
+        if let Some(pitch) = options.pitch {
+            let audio_pitch = (pitch.clamp(MIN_PITCH_HZ, MAX_PITCH_HZ) / NORMAL_PITCH_HZ).min(2.0) as f64;
+            synthesizer_options
+                .SetAudioPitch(audio_pitch)
+                .map_err(|e| engine_failure(format!("Failed to set pitch: {:?}", e)))?;
+        }
+
+        // 合成到内存中的音频流；SSML 的 xml:lang 使用当前音色的语言
+        let operation = match &input {
+            SpeechInput::Text(text) => synthesizer.SynthesizeTextToStreamAsync(&HSTRING::from(text.as_str())),
+            SpeechInput::Ssml(ssml) => {
+                let language = synthesizer
+                    .Voice()
+                    .and_then(|voice| voice.Language())
+                    .map(|language| language.to_string())
+                    .unwrap_or_else(|_| "en-US".to_string());
+                synthesizer.SynthesizeSsmlToStreamAsync(&HSTRING::from(ssml.to_document(Some(&language))))
+            }
+        };
+        let stream = operation
+            .map_err(|e| engine_failure(format!("Failed to synthesize speech: {:?}", e)))?
+            .await
+            .map_err(|e| engine_failure(format!("Failed to wait for speech synthesis: {:?}", e)))?;
+        let content_type = stream
+            .ContentType()
+            .map_err(|e| engine_failure(format!("Failed to get speech stream content type: {:?}", e)))?;
+        let source = MediaSource::CreateFromStream(&stream, &content_type)
+            .map_err(|e| engine_failure(format!("Failed to create media source: {:?}", e)))?;
+
+        let player = MediaPlayer::new()
+            .map_err(|e| engine_failure(format!("Failed to create media player: {:?}", e)))?;
+        player
+            .SetSource(&source)
+            .map_err(|e| engine_failure(format!("Failed to set media source: {:?}", e)))?;
+        Ok::<MediaPlayer, (TtsError, String)>(player)
+    }
+    .await;
+
+    let player = match player {
+        Ok(player) => player,
+        Err((code, message)) => {
+            return TtsResult::failure(code, message);
+        }
+    };
+
+    // 播放结束时移出表并发送朗读完成事件到前端；已被 stop_speaking 移出表时不再发送
+    let process_id_clone = process_id.clone();
+    let app_handle_clone = app_handle.clone();
+    let ended = player.MediaEnded(&TypedEventHandler::new(move |_, _| {
+        if TTS_PLAYERS.lock().unwrap().remove(&process_id_clone).is_some() {
+            let _ = app_handle_clone.emit("tts-finished", process_id_clone.clone());
+        }
+        Ok(())
+    }));
+
+    // 播放失败时发送 tts-error，随后同样发送 tts-finished
+    let process_id_clone = process_id.clone();
+    let app_handle_clone = app_handle.clone();
+    let failed = player.MediaFailed(&TypedEventHandler::new(move |_, args: windows::core::Ref<MediaPlayerFailedEventArgs>| {
+        if TTS_PLAYERS.lock().unwrap().remove(&process_id_clone).is_some() {
+            let error_message = args
+                .as_ref()
+                .and_then(|args| args.ErrorMessage().ok())
+                .map(|message| message.to_string())
+                .unwrap_or_else(|| "Playback failed".to_string());
+            let _ = app_handle_clone.emit("tts-error", TtsErrorEvent {
+                process_id: process_id_clone.clone(),
+                error_message,
+            });
+            let _ = app_handle_clone.emit("tts-finished", process_id_clone.clone());
+        }
+        Ok(())
+    }));
+
+    TTS_PLAYERS.lock().unwrap().insert(process_id.clone(), player.clone());
+
+    if let Err(e) = ended.and(failed).and_then(|_| player.Play()) {
+        TTS_PLAYERS.lock().unwrap().remove(&process_id);
+        return TtsResult::failure(TtsError::EngineFailure, format!("Failed to start TTS: {:?}", e));
+    }
+
+    let _ = app_handle.emit("tts-started", process_id.clone());
+
+    TtsResult {
+        success: true,
+        process_id: Some(process_id),
+        output_path: None,
+        error_message: None,
+        error_code: None,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn set_speech_paused_windows(process_id: &str, paused: bool) -> std::result::Result<(), (TtsError, String)> {
+    let player = match TTS_PLAYERS.lock().unwrap().get(process_id) {
+        Some(player) => player.clone(),
+        None => return Err((TtsError::NotSpeaking, format!("No active TTS process with id {}", process_id))),
+    };
+    
+    let result = if paused { player.Pause() } else { player.Play() };
+    result.map_err(|e| {
+        let action = if paused { "pause" } else { "resume" };
+        (TtsError::EngineFailure, format!("Failed to {} TTS: {:?}", action, e))
+    })
+}
+
+#[cfg(target_os = "windows")]
+async fn stop_speaking_windows(process_id: String) -> TtsResult {
+    let player = TTS_PLAYERS.lock().unwrap().remove(&process_id);
+
+    if let Some(player) = player {
+        // 只停止对应的朗读
+        let _ = player.Pause();
+        let _ = player.Close();
+
+        TtsResult {
+            success: true,
+            process_id: None,
+            output_path: None,
+            error_message: None,
+            error_code: None,
+        }
+    } else {
+        TtsResult::failure(TtsError::NotSpeaking, format!("No active TTS process with id {}", process_id))
+    }
+}
+
+#[cfg(target_os = "windows")]
+async fn stop_all_speaking_windows() -> TtsResult {
+    let players: Vec<MediaPlayer> = TTS_PLAYERS
+        .lock()
+        .unwrap()
+        .drain()
+        .map(|(_, player)| player)
+        .collect();
+
+    for player in players {
+        let _ = player.Pause();
+        let _ = player.Close();
+    }
+
+    TtsResult {
+        success: true,
+        process_id: None,
+        output_path: None,
+        error_message: None,
+        error_code: None,
+    }
+}
+
+/// 列出 Windows 上的音色及其语言
+/// 音色名称使用 DisplayName，标识符使用 Id，speak_text 的 voice 两者都接受
+#[cfg(target_os = "windows")]
+fn windows_voices() -> Result<Vec<(VoiceInfo, String)>, String> {
+    let voices = SpeechSynthesizer::AllVoices().map_err(|e| format!("Failed to get voices: {:?}", e))?;
+    Ok(voices
+        .into_iter()
+        .filter_map(|voice| {
+            Some((
+                VoiceInfo {
+                    name: voice.DisplayName().ok()?.to_string(),
+                    identifier: voice.Id().ok()?.to_string(),
+                },
+                voice.Language().ok()?.to_string(),
+            ))
+        })
+        .collect())
+}
+
+#[cfg(target_os = "windows")]
+async fn get_supported_languages_windows() -> LanguageResult {
+    match windows_voices() {
+        Ok(voices) => {
+            let mut languages: Vec<String> = voices.into_iter().map(|(_, language)| language).collect();
+            languages.sort();
+            languages.dedup();
+            LanguageResult {
+                languages,
+                success: true,
+                error_message: None,
+            }
+        }
+        Err(e) => LanguageResult {
+            languages: vec![],
+            success: false,
+            error_message: Some(e),
+        },
+    }
+}
+
+#[cfg(target_os = "windows")]
+async fn get_voices_for_language_windows(language: String) -> VoiceResult {
+    match windows_voices() {
+        Ok(voices) => VoiceResult {
+            voices: voices
+                .into_iter()
+                .filter(|(_, voice_language)| voice_matches_language(voice_language, &language))
+                .map(|(voice, _)| voice)
+                .collect(),
+            success: true,
+            error_message: None,
+        },
+        Err(e) => VoiceResult {
+            voices: vec![],
+            success: false,
+            error_message: Some(e),
+        },
+    }
+}
+
+/// 构建 spd-say 命令；--wait 使 spd-say 在朗读结束后才退出，以便像 say 一样等待进程结束
+/// ssml 为 true 时 text 为 SSML 文档
+#[cfg(target_os = "linux")]
+fn spd_say_command(text: &str, ssml: bool, options: &SpeakOptions) -> Command {
+    let mut cmd = Command::new("spd-say");
+    cmd.arg("--wait");
+    if ssml {
+        cmd.arg("-x");
+    }
+    if let Some(voice_name) = &options.voice {
+        cmd.arg("-y").arg(voice_name);
+    }
+    // 语速和音量都是 -100~100 的相对值，0 为默认值
+    if let Some(rate_wpm) = options.rate_wpm {
+        let rate = (rate_wpm.clamp(MIN_RATE_WPM, MAX_RATE_WPM) as f32 / SPD_SAY_NORMAL_RATE_WPM - 1.0) * 100.0;
+        cmd.arg("-r").arg((rate.clamp(-100.0, 100.0).round() as i32).to_string());
+    }
+    if let Some(volume) = options.volume {
+        let volume = volume.clamp(0.0, 1.0) * 200.0 - 100.0;
+        cmd.arg("-i").arg((volume.round() as i32).to_string());
+    }
+    // 音高同样为 -100~100，每 100 相当于一个八度
+    if let Some(pitch) = options.pitch {
+        let pitch = pitch_octaves(pitch) * 100.0;
+        cmd.arg("-p").arg((pitch.clamp(-100.0, 100.0).round() as i32).to_string());
+    }
+    cmd.arg("--").arg(text);
+    cmd
+}
+
+/// 构建 espeak-ng 命令，语速直接使用每分钟单词数
+#[cfg(target_os = "linux")]
+fn espeak_ng_command(text: &str, ssml: bool, options: &SpeakOptions) -> Command {
+    let mut cmd = Command::new("espeak-ng");
+    if ssml {
+        cmd.arg("-m");
+    }
+    if let Some(voice_name) = &options.voice {
+        cmd.arg("-v").arg(voice_name);
+    }
+    if let Some(rate_wpm) = options.rate_wpm {
+        cmd.arg("-s").arg(rate_wpm.clamp(MIN_RATE_WPM, MAX_RATE_WPM).to_string());
+    }
+    // 振幅 0~200，默认 100
+    if let Some(volume) = options.volume {
+        cmd.arg("-a").arg(((volume.clamp(0.0, 1.0) * 200.0).round() as u32).to_string());
+    }
+    // 音高 0~99，默认 50，每 50 相当于一个八度
+    if let Some(pitch) = options.pitch {
+        let pitch = 50.0 + pitch_octaves(pitch) * 50.0;
+        cmd.arg("-p").arg((pitch.clamp(0.0, 99.0).round() as u32).to_string());
+    }
+    cmd.arg("--").arg(text);
+    cmd
+}
+
+/// 优先使用 speech-dispatcher 的 spd-say，未安装时使用 espeak-ng
+#[cfg(target_os = "linux")]
+async fn speak_text_linux(app_handle: tauri::AppHandle, input: SpeechInput, options: SpeakOptions, process_id: String) -> TtsResult {
+    use std::process::Stdio;
+    
+    let (text, ssml) = match input {
+        SpeechInput::Text(text) => (text, false),
+        SpeechInput::Ssml(ssml) => (ssml.to_document(None), true),
+    };
+    
+    let spawn = |mut cmd: Command| cmd.stdout(Stdio::null()).stderr(Stdio::null()).spawn();
+    let spawned = match spawn(spd_say_command(&text, ssml, &options)) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            spawn(espeak_ng_command(&text, ssml, &options)).map(|child| (child, "espeak-ng"))
+        }
+        spawned => spawned.map(|child| (child, "spd-say")),
+    };
+    
+    match spawned {
+        Ok((child, program)) => {
+            // 记录子进程，stop_speaking 根据 process_id 只终止对应的朗读
+            TTS_PROCESSES.lock().unwrap().insert(process_id.clone(), child);
+            watch_tts_process(app_handle.clone(), process_id.clone(), program);
+            
+            let _ = app_handle.emit("tts-started", process_id.clone());
+            
+            TtsResult {
+                success: true,
+                process_id: Some(process_id),
+                output_path: None,
+                error_message: None,
+                error_code: None,
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            TtsResult::failure(TtsError::BinaryNotFound, LINUX_TTS_NOT_FOUND.to_string())
+        }
+        Err(e) => TtsResult::failure(TtsError::EngineFailure, format!("Failed to start TTS: {}", e)),
+    }
+}
+
+/// 终止 spd-say 进程后 speech-dispatcher 仍会读完已提交的文本，需要另外取消
+/// spd-say 不能只取消某一条，因此会一并取消其他应用提交的朗读；未安装 spd-say 时什么也不做
+#[cfg(target_os = "linux")]
+fn cancel_speech_dispatcher() {
+    let _ = Command::new("spd-say").arg("--cancel").output();
+}
+
+/// 解析 spd-say -L 的输出：表头之后每行为 "名称 语言 变体"，名称中可能含有空格
+#[cfg(target_os = "linux")]
+fn parse_spd_say_voices(output: &str) -> Vec<(VoiceInfo, String)> {
+    output
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("NAME"))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().rev();
+            let _variant = fields.next()?;
+            let language = fields.next()?;
+            let name = fields.rev().collect::<Vec<&str>>().join(" ");
+            if name.is_empty() {
+                return None;
+            }
+            Some((
+                VoiceInfo {
+                    name: name.clone(),
+                    identifier: name,
+                },
+                language.replace('_', "-"),
+            ))
+        })
+        .collect()
+}
+
+/// 解析 espeak-ng --voices 的输出：表头之后每行为 "优先级 语言 年龄/性别 名称 文件 其他语言"
+/// espeak-ng -v 既接受名称也接受文件，标识符使用文件
+#[cfg(target_os = "linux")]
+fn parse_espeak_ng_voices(output: &str) -> Vec<(VoiceInfo, String)> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 5 {
+                return None;
+            }
+            Some((
+                VoiceInfo {
+                    name: fields[3].to_string(),
+                    identifier: fields[4].to_string(),
+                },
+                fields[1].replace('_', "-"),
+            ))
+        })
+        .collect()
+}
+
+/// 列出 Linux 上可用的音色及其语言，与朗读一样优先使用 spd-say
+#[cfg(target_os = "linux")]
+fn linux_voices() -> Result<Vec<(VoiceInfo, String)>, String> {
+    match Command::new("spd-say").arg("-L").output() {
+        Ok(output) if output.status.success() => Ok(parse_spd_say_voices(&String::from_utf8_lossy(&output.stdout))),
+        Ok(output) => Err(format!(
+            "Failed to list voices with spd-say: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            match Command::new("espeak-ng").arg("--voices").output() {
+                Ok(output) if output.status.success() => {
+                    Ok(parse_espeak_ng_voices(&String::from_utf8_lossy(&output.stdout)))
                 }
+                Ok(output) => Err(format!(
+                    "Failed to list voices with espeak-ng: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                )),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(LINUX_TTS_NOT_FOUND.to_string()),
+                Err(e) => Err(format!("Failed to execute espeak-ng: {}", e)),
             }
         }
-        Err(e) => {
-            VoiceResult {
-                voices: vec![],
-                success: false,
-                error_message: Some(format!("Failed to execute say command: {}", e)),
+        Err(e) => Err(format!("Failed to execute spd-say: {}", e)),
+    }
+}
+
+#[cfg(target_os = "linux")]
+async fn get_supported_languages_linux() -> LanguageResult {
+    match linux_voices() {
+        Ok(voices) => {
+            let mut languages: Vec<String> = voices.into_iter().map(|(_, language)| language).collect();
+            languages.sort();
+            languages.dedup();
+            LanguageResult {
+                languages,
+                success: true,
+                error_message: None,
             }
         }
+        Err(e) => LanguageResult {
+            languages: vec![],
+            success: false,
+            error_message: Some(e),
+        },
+    }
+}
+
+#[cfg(target_os = "linux")]
+async fn get_voices_for_language_linux(language: String) -> VoiceResult {
+    match linux_voices() {
+        Ok(voices) => VoiceResult {
+            voices: voices
+                .into_iter()
+                .filter(|(_, voice_language)| voice_matches_language(voice_language, &language))
+                .map(|(voice, _)| voice)
+                .collect(),
+            success: true,
+            error_message: None,
+        },
+        Err(e) => VoiceResult {
+            voices: vec![],
+            success: false,
+            error_message: Some(e),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssml::SsmlNode;
+
+    fn dictionary(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries.iter().map(|(word, respelling)| (word.to_string(), respelling.to_string())).collect()
+    }
+
+    #[test]
+    fn pronunciations_match_whole_words_case_insensitively() {
+        let pronunciations = dictionary(&[("SQL", "sequel"), ("Nginx", "engine x")]);
+        assert_eq!(
+            apply_pronunciations("Run sql on NGINX, not MySQL or nginxd.", &pronunciations),
+            "Run sequel on engine x, not MySQL or nginxd."
+        );
+    }
+
+    #[test]
+    fn pronunciations_prefer_longest_entry() {
+        let pronunciations = dictionary(&[("New", "noo"), ("New York", "nu york"), ("York", "yawk")]);
+        assert_eq!(apply_pronunciations("New York and York, New", &pronunciations), "nu york and yawk, noo");
+    }
+
+    #[test]
+    fn pronunciations_match_cjk_inside_sentences() {
+        // 中日韩文字之间没有词边界，词条可以在句中任意位置匹配
+        let pronunciations = dictionary(&[("重庆", "虫庆"), ("Tauri", "tow ree")]);
+        assert_eq!(apply_pronunciations("我在重庆用Tauri开发", &pronunciations), "我在虫庆用tow ree开发");
+    }
+
+    #[test]
+    fn empty_pronunciation_entries_are_ignored() {
+        assert_eq!(apply_pronunciations("unchanged", &HashMap::new()), "unchanged");
+        let pronunciations = dictionary(&[("", "x"), ("  ", "y")]);
+        assert_eq!(apply_pronunciations("a  b", &pronunciations), "a  b");
+    }
+
+    #[test]
+    fn pronunciations_leave_ssml_structure_alone() {
+        let pronunciations = dictionary(&[("SQL", "sequel")]);
+        let ssml = Ssml::parse("<speak>SQL<break time=\"1s\"/><emphasis>sql</emphasis></speak>")
+            .unwrap()
+            .map_text(&|text| apply_pronunciations(text, &pronunciations));
+        assert_eq!(
+            ssml.nodes,
+            vec![
+                SsmlNode::Text("sequel".to_string()),
+                SsmlNode::Break(1000),
+                SsmlNode::Emphasis(vec![SsmlNode::Text("sequel".to_string())]),
+            ]
+        );
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn say_input_substitutes_pronunciations_before_speaking() {
+        let pronunciations = dictionary(&[("SQL", "sequel"), ("Tauri", "[[inpt PHON]]tAWrIY[[inpt TEXT]]")]);
+        let text = |text: &str| say_input_text(&SpeechInput::Text(text.to_string()), &pronunciations, DEFAULT_SAY_RATE_WPM);
+
+        assert_eq!(text("Query SQL"), "Query sequel");
+        assert_eq!(text("Tauri [[volm 0]]"), "[[inpt PHON]]tAWrIY[[inpt TEXT]] [ [volm 0] ]");
+
+        let ssml = Ssml::parse("<speak>SQL<break time=\"1s\"/>Tauri</speak>").unwrap();
+        assert_eq!(
+            say_input_text(&SpeechInput::Ssml(ssml), &pronunciations, DEFAULT_SAY_RATE_WPM),
+            "sequel [[slnc 1000]] [[inpt PHON]]tAWrIY[[inpt TEXT]]"
+        );
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn escapes_say_commands() {
+        assert_eq!(escape_say_commands("[[volm 0]] quiet"), "[ [volm 0] ] quiet");
+        assert_eq!(escape_say_commands("[[[slnc 1]]]"), "[ [ [slnc 1] ] ]");
+        assert_eq!(escape_say_commands("a[b]c [ [ok] ]"), "a[b]c [ [ok] ]");
+        assert!(!escape_say_commands("[[[[x]]]]").contains("[["));
+        assert!(!escape_say_commands("[[[[x]]]]").contains("]]"));
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn ssml_converts_to_escaped_say_markup() {
+        let ssml = Ssml::parse(
+            "<speak>say [[rset]]<break time=\"300ms\"/><emphasis>two words</emphasis>\
+             <prosody rate=\"x-slow\">slow</prosody></speak>",
+        )
+        .unwrap();
+        let mut markup = String::new();
+        ssml_to_say_markup(&ssml.nodes, &HashMap::new(), 200, false, &mut markup);
+        assert_eq!(
+            markup,
+            "say [ [rset] ] [[slnc 300]]  [[emph +]] two [[emph +]] words  [[rate 100]] slow [[rate 200]] "
+        );
+
+        // 嵌入命令只在 markup 为 true 时生效，转换回 say 命令后与原文一致
+        let mut markup = String::new();
+        ssml_to_say_markup(&Ssml::from_say_markup("a [[slnc 50]] b [[volm 0]]").nodes, &HashMap::new(), 200, false, &mut markup);
+        assert_eq!(markup, "a  [[slnc 50]]  b [ [volm 0] ]");
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn say_text_keeps_respelling_commands_and_escapes_input() {
+        let pronunciations = dictionary(&[("Tauri", "[[inpt PHON]]tAWrIY[[inpt TEXT]]")]);
+        assert_eq!(
+            say_text("[[volm 0]] Tauri app", &pronunciations),
+            "[ [volm 0] ] [[inpt PHON]]tAWrIY[[inpt TEXT]] app"
+        );
+
+        let ssml = Ssml::parse("<speak>Tauri [[volm 0]]<emphasis>Tauri [[rset]]</emphasis></speak>").unwrap();
+        let mut markup = String::new();
+        ssml_to_say_markup(&ssml.nodes, &pronunciations, 200, false, &mut markup);
+        assert_eq!(
+            markup,
+            "[[inpt PHON]]tAWrIY[[inpt TEXT]] [ [volm 0] ] [[emph +]] [[inpt PHON]]tAWrIY[[inpt TEXT]] [[emph +]] [ [rset] ] "
+        );
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn file_synthesis_escapes_input_after_pronunciations() {
+        let pronunciations = dictionary(&[("Tauri", "[[inpt PHON]]tAWrIY[[inpt TEXT]]")]);
+        let cmd = say_to_file_command(
+            "[[rate 1]] Tauri",
+            &pronunciations,
+            Some("Alex"),
+            std::path::Path::new("/tmp/speech.aiff"),
+            &["--file-format=AIFF"],
+        );
+        let args: Vec<_> = cmd.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        assert_eq!(
+            args,
+            [
+                "-v",
+                "Alex",
+                "-o",
+                "/tmp/speech.aiff",
+                "--file-format=AIFF",
+                "[ [rate 1] ] [[inpt PHON]]tAWrIY[[inpt TEXT]]",
+            ]
+        );
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn parses_legacy_voice_lines() {
+        assert_eq!(
+            parse_voice_line("Alex                en_US    # Most people recognize me by my voice."),
+            Some(("Alex".to_string(), "en-US".to_string()))
+        );
+        assert_eq!(
+            parse_voice_line("Ting-Ting           zh_CN    # 你好，我叫婷婷。"),
+            Some(("Ting-Ting".to_string(), "zh-CN".to_string()))
+        );
+        assert_eq!(
+            parse_voice_line("Bad News            en_US    # The light you see at the end of the tunnel..."),
+            Some(("Bad News".to_string(), "en-US".to_string()))
+        );
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn parses_current_voice_lines() {
+        assert_eq!(
+            parse_voice_line("Rocko (English (US)) en_US    # Hello! My name is Rocko."),
+            Some(("Rocko (English (US))".to_string(), "en-US".to_string()))
+        );
+        assert_eq!(
+            parse_voice_line("Eddy (Spanish (Latin America)) es_419   # ¡Hola! Me llamo Eddy. #1"),
+            Some(("Eddy (Spanish (Latin America))".to_string(), "es-419".to_string()))
+        );
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn rejects_malformed_voice_lines() {
+        assert_eq!(parse_voice_line(""), None);
+        assert_eq!(parse_voice_line("Alex                en_US"), None);
+        assert_eq!(parse_voice_line("Alex    # no language code"), None);
+        assert_eq!(parse_voice_line("Good News           # missing language"), None);
+        assert_eq!(parse_voice_line("en_US    # missing name"), None);
+    }
+
+    fn voice(name: &str) -> VoiceInfo {
+        VoiceInfo {
+            name: name.to_string(),
+            identifier: name.to_string(),
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn groups_voices_by_sorted_language() {
+        let voices = vec![
+            (voice("Ting-Ting"), "zh-CN".to_string()),
+            (voice("Samantha"), "en-US".to_string()),
+            (voice("Daniel"), "en-GB".to_string()),
+            (voice("Alex"), "en-US".to_string()),
+        ];
+        let groups = group_voices_by_language(voices);
+        let summary: Vec<(&str, Vec<&str>)> = groups
+            .iter()
+            .map(|group| (group.language.as_str(), group.voices.iter().map(|v| v.name.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("en-GB", vec!["Daniel"]),
+                ("en-US", vec!["Samantha", "Alex"]),
+                ("zh-CN", vec!["Ting-Ting"]),
+            ]
+        );
+        assert!(group_voices_by_language(vec![]).is_empty());
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn groups_say_output_fixture() {
+        let output = "\
+Alex                en_US    # Most people recognize me by my voice.
+Daniel              en_GB    # Hello, my name is Daniel.
+Eddy (Spanish (Latin America)) es_419   # ¡Hola! Me llamo Eddy.
+Rocko (English (US)) en_US    # Hello! My name is Rocko.
+Ting-Ting           zh_CN    # 你好，我叫婷婷。
+";
+        let groups = group_voices_by_language(parse_say_voices(output));
+        let languages: Vec<&str> = groups.iter().map(|group| group.language.as_str()).collect();
+        assert_eq!(languages, vec!["en-GB", "en-US", "es-419", "zh-CN"]);
+        let en_us: Vec<&str> = groups[1].voices.iter().map(|v| v.identifier.as_str()).collect();
+        assert_eq!(en_us, vec!["Alex", "Rocko (English (US))"]);
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn pitch_maps_to_pbas_notes() {
+        assert!((pitch_to_pbas(440.0 / 2.0) - 57.0).abs() < 1e-4);
+        assert_eq!(pitch_to_pbas(1000.0), pitch_to_pbas(MAX_PITCH_HZ));
+        assert_eq!(pitch_to_pbas(1.0), pitch_to_pbas(MIN_PITCH_HZ));
+        assert!((1.0..=127.0).contains(&pitch_to_pbas(MIN_PITCH_HZ)));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn pitch_maps_to_linux_arguments() {
+        let argument = |cmd: &Command, flag: &str| {
+            let args: Vec<_> = cmd.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+            args.iter().position(|arg| arg == flag).map(|i| args[i + 1].clone())
+        };
+        let options = |pitch| SpeakOptions {
+            pitch: Some(pitch),
+            ..Default::default()
+        };
+
+        assert_eq!(argument(&spd_say_command("hi", false, &options(NORMAL_PITCH_HZ)), "-p").as_deref(), Some("0"));
+        assert_eq!(argument(&spd_say_command("hi", false, &options(240.0)), "-p").as_deref(), Some("100"));
+        assert_eq!(argument(&spd_say_command("hi", false, &options(10.0)), "-p").as_deref(), Some("-100"));
+        assert_eq!(argument(&espeak_ng_command("hi", false, &options(NORMAL_PITCH_HZ)), "-p").as_deref(), Some("50"));
+        assert_eq!(argument(&espeak_ng_command("hi", false, &options(60.0)), "-p").as_deref(), Some("0"));
+        assert_eq!(argument(&espeak_ng_command("hi", false, &options(MAX_PITCH_HZ)), "-p").as_deref(), Some("99"));
+        assert_eq!(argument(&espeak_ng_command("hi", false, &SpeakOptions::default()), "-p"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn rate_is_clamped_to_the_documented_range() {
+        let argument = |cmd: &Command, flag: &str| {
+            let args: Vec<_> = cmd.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+            args.iter().position(|arg| arg == flag).map(|i| args[i + 1].clone())
+        };
+        let options = |rate_wpm| SpeakOptions {
+            rate_wpm: Some(rate_wpm),
+            ..Default::default()
+        };
+
+        assert_eq!(argument(&espeak_ng_command("hi", false, &options(10)), "-s").as_deref(), Some("50"));
+        assert_eq!(argument(&espeak_ng_command("hi", false, &options(60)), "-s").as_deref(), Some("60"));
+        assert_eq!(argument(&espeak_ng_command("hi", false, &options(900)), "-s").as_deref(), Some("500"));
+        assert_eq!(argument(&spd_say_command("hi", false, &options(180)), "-r").as_deref(), Some("0"));
+        assert_eq!(argument(&spd_say_command("hi", false, &options(50)), "-r").as_deref(), Some("-72"));
+        assert_eq!(argument(&spd_say_command("hi", false, &options(500)), "-r").as_deref(), Some("100"));
+    }
+}